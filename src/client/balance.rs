@@ -0,0 +1,431 @@
+//! Client Load Balancing
+//!
+//! A `BalancingConnector` maps logical host names to lists of addresses, and
+//! spreads the connections to each name over its addresses. It is a
+//! `NetworkConnector`, so it goes under a `Pool` as usual, which keeps its
+//! connections by logical name. TLS handshakes use the logical name, and the
+//! `Host` header is taken from the URL as always.
+//!
+//! ```no_run
+//! use hyper::Client;
+//! use hyper::client::Pool;
+//! use hyper::client::balance::{BalancingConnector, Strategy};
+//! use hyper::net::HttpConnector;
+//!
+//! let mut connector = BalancingConnector::new(HttpConnector::default(),
+//!                                             Strategy::LeastOutstanding);
+//! connector.add_service("users.internal", vec![
+//!     "10.0.0.1:8080".parse().unwrap(),
+//!     "10.0.0.2:8080".parse().unwrap(),
+//! ]);
+//! let client = Client::with_connector(Pool::with_connector(Default::default(), connector));
+//! client.get("http://users.internal/me").send().unwrap();
+//! ```
+//!
+//! Addresses that fail to connect, or whose connections are reset, aborted
+//! or cut short while reading or writing, are counted as failing. After
+//! enough failures in a row, an address is ejected for a while, and left out
+//! of the choices. If every address of a name is ejected, all of them are
+//! tried anyway.
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, Shutdown};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use time::precise_time_ns;
+
+use net::{AddrConnector, ConnectTiming, DefaultConnector, NetworkConnector, NetworkStream,
+          StreamCloser, TlsInfo};
+
+/// How a `BalancingConnector` picks an address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Each address in turn.
+    RoundRobin,
+    /// The address with the fewest open connections.
+    LeastOutstanding,
+    /// The one with fewer open connections of two random addresses.
+    RandomTwoChoices,
+}
+
+/// A `NetworkConnector` spreading connections to logical hosts over their
+/// addresses.
+///
+/// The port of a request to a logical host is ignored, in favor of the
+/// ports of its addresses. Hosts that were not added as services are
+/// connected to directly.
+pub struct BalancingConnector<C = DefaultConnector> {
+    connector: C,
+    strategy: Strategy,
+    max_failures: u32,
+    ejection: Duration,
+    services: HashMap<String, Service>,
+    rng: Mutex<u64>,
+}
+
+struct Service {
+    backends: Vec<Arc<Backend>>,
+    next: AtomicUsize,
+}
+
+struct Backend {
+    addr: SocketAddr,
+    outstanding: AtomicUsize,
+    health: Mutex<Health>,
+}
+
+struct Health {
+    failures: u32,
+    ejected_until: u64,
+}
+
+impl<C: AddrConnector> BalancingConnector<C> {
+    /// Create a balancer connecting through `connector`, picking addresses
+    /// by `strategy`.
+    ///
+    /// Addresses are ejected for 30 seconds after 5 failures in a row.
+    pub fn new(connector: C, strategy: Strategy) -> BalancingConnector<C> {
+        BalancingConnector {
+            connector: connector,
+            strategy: strategy,
+            max_failures: 5,
+            ejection: Duration::from_secs(30),
+            services: HashMap::new(),
+            rng: Mutex::new(precise_time_ns() | 1),
+        }
+    }
+
+    /// Map the logical `host` to `addrs`.
+    pub fn add_service(&mut self, host: &str, addrs: Vec<SocketAddr>) {
+        self.services.insert(host.to_owned(), Service {
+            backends: addrs.into_iter().map(|addr| Arc::new(Backend {
+                addr: addr,
+                outstanding: AtomicUsize::new(0),
+                health: Mutex::new(Health {
+                    failures: 0,
+                    ejected_until: 0,
+                }),
+            })).collect(),
+            next: AtomicUsize::new(0),
+        });
+    }
+
+    /// Eject addresses for `duration` after `max_failures` failures in a row.
+    pub fn set_ejection(&mut self, max_failures: u32, duration: Duration) {
+        self.max_failures = max_failures;
+        self.ejection = duration;
+    }
+
+    fn random(&self, below: usize) -> usize {
+        let mut x = self.rng.lock().unwrap();
+        *x ^= *x << 13;
+        *x ^= *x >> 7;
+        *x ^= *x << 17;
+        (*x % below as u64) as usize
+    }
+
+    // The order to try the addresses of `service` in, best first.
+    fn order(&self, service: &Service) -> Vec<Arc<Backend>> {
+        let now = precise_time_ns();
+        let mut healthy: Vec<Arc<Backend>> = service.backends.iter()
+            .filter(|backend| backend.health.lock().unwrap().ejected_until <= now)
+            .cloned()
+            .collect();
+        if healthy.is_empty() {
+            healthy = service.backends.clone();
+        }
+        if healthy.is_empty() {
+            return healthy;
+        }
+
+        let start = service.next.fetch_add(1, Ordering::SeqCst) % healthy.len();
+        let mut order: Vec<Arc<Backend>> = healthy[start..].iter()
+            .chain(healthy[..start].iter())
+            .cloned()
+            .collect();
+        match self.strategy {
+            Strategy::RoundRobin => (),
+            Strategy::LeastOutstanding => {
+                // a stable sort keeps the round robin order among equals
+                order.sort_by(|a, b| {
+                    a.outstanding.load(Ordering::SeqCst).cmp(&b.outstanding.load(Ordering::SeqCst))
+                });
+            },
+            Strategy::RandomTwoChoices => {
+                let len = order.len();
+                let a = self.random(len);
+                let b = if len > 1 { (a + 1 + self.random(len - 1)) % len } else { a };
+                let pick = if order[b].outstanding.load(Ordering::SeqCst) <
+                        order[a].outstanding.load(Ordering::SeqCst) {
+                    b
+                } else {
+                    a
+                };
+                order.swap(0, pick);
+            }
+        }
+        order
+    }
+}
+
+impl Backend {
+    fn report(&self, success: bool, max_failures: u32, ejection: Duration) {
+        let mut health = self.health.lock().unwrap();
+        if success {
+            health.failures = 0;
+            return;
+        }
+        health.failures += 1;
+        if health.failures >= max_failures {
+            debug!("ejecting {} after {} failures", self.addr, health.failures);
+            health.failures = 0;
+            health.ejected_until = precise_time_ns() + ejection.as_secs() * 1_000_000_000 +
+                ejection.subsec_nanos() as u64;
+        }
+    }
+}
+
+impl<C> NetworkConnector for BalancingConnector<C>
+where C: AddrConnector, C::Stream: NetworkStream + Send {
+    type Stream = BalancedStream<C::Stream>;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<BalancedStream<C::Stream>> {
+        self.connect_timed(host, port, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_timed(&self, host: &str, port: u16, scheme: &str)
+                     -> ::Result<(BalancedStream<C::Stream>, ConnectTiming)> {
+        let service = match self.services.get(host) {
+            Some(service) => service,
+            None => {
+                let (stream, timing) = try!(self.connector.connect_timed(host, port, scheme));
+                return Ok((BalancedStream {
+                    inner: stream,
+                    backend: None,
+                    max_failures: self.max_failures,
+                    ejection: self.ejection,
+                }, timing));
+            }
+        };
+
+        let mut last_err = None;
+        for backend in self.order(service) {
+            debug!("connecting to {} at {}", host, backend.addr);
+            match self.connector.connect_addr_timed(host, backend.addr, scheme) {
+                Ok((stream, timing)) => {
+                    backend.report(true, self.max_failures, self.ejection);
+                    backend.outstanding.fetch_add(1, Ordering::SeqCst);
+                    return Ok((BalancedStream {
+                        inner: stream,
+                        backend: Some(backend),
+                        max_failures: self.max_failures,
+                        ejection: self.ejection,
+                    }, timing));
+                },
+                Err(e) => {
+                    debug!("connecting to {} failed: {}", backend.addr, e);
+                    backend.report(false, self.max_failures, self.ejection);
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput,
+                           format!("no addresses for {}", host)).into()
+        }))
+    }
+}
+
+/// A stream of a `BalancingConnector`, counted as outstanding for its
+/// address until dropped.
+pub struct BalancedStream<S> {
+    inner: S,
+    backend: Option<Arc<Backend>>,
+    max_failures: u32,
+    ejection: Duration,
+}
+
+impl<S> BalancedStream<S> {
+    /// The address picked for this stream, if its host is a service.
+    pub fn backend_addr(&self) -> Option<SocketAddr> {
+        self.backend.as_ref().map(|backend| backend.addr)
+    }
+
+    fn check<T>(&self, result: io::Result<T>) -> io::Result<T> {
+        if let (&Err(ref e), Some(backend)) = (&result, self.backend.as_ref()) {
+            if is_connection_failure(e) {
+                backend.report(false, self.max_failures, self.ejection);
+            }
+        }
+        result
+    }
+}
+
+// Only a broken connection counts against its address. Timeouts and
+// would-blocks say more about the request than about the address.
+fn is_connection_failure(e: &io::Error) -> bool {
+    match e.kind() {
+        io::ErrorKind::ConnectionReset |
+        io::ErrorKind::ConnectionAborted |
+        io::ErrorKind::BrokenPipe |
+        io::ErrorKind::UnexpectedEof => true,
+        _ => false
+    }
+}
+
+impl<S> Drop for BalancedStream<S> {
+    fn drop(&mut self) {
+        if let Some(ref backend) = self.backend {
+            backend.outstanding.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<S: Read> Read for BalancedStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.inner.read(buf);
+        self.check(result)
+    }
+}
+
+impl<S: Write> Write for BalancedStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner.write(buf);
+        self.check(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.inner.flush();
+        self.check(result)
+    }
+}
+
+impl<S: NetworkStream> NetworkStream for BalancedStream<S> {
+    #[inline]
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    #[cfg(feature = "timeouts")]
+    #[inline]
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(dur)
+    }
+
+    #[cfg(feature = "timeouts")]
+    #[inline]
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_write_timeout(dur)
+    }
+
+    #[inline]
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.inner.close(how)
+    }
+
+    #[inline]
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        self.inner.closer()
+    }
+
+    #[inline]
+    fn tls_info(&self) -> Option<TlsInfo> {
+        self.inner.tls_info()
+    }
+
+    #[inline]
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        self.inner.connect_timing()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::{SocketAddr, TcpListener};
+    use std::time::Duration;
+
+    use net::{HttpConnector, NetworkConnector};
+    use super::{BalancingConnector, Strategy, is_connection_failure};
+
+    fn listen() -> (TcpListener, SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        (listener, addr)
+    }
+
+    // an address that refuses connections
+    fn dead() -> SocketAddr {
+        listen().1
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let (_a, a) = listen();
+        let (_b, b) = listen();
+        let mut connector = BalancingConnector::new(HttpConnector::default(), Strategy::RoundRobin);
+        connector.add_service("service", vec![a, b]);
+
+        let picks: Vec<_> = (0..4).map(|_| {
+            connector.connect("service", 80, "http").unwrap().backend_addr().unwrap()
+        }).collect();
+        assert_eq!(picks, vec![a, b, a, b]);
+    }
+
+    #[test]
+    fn test_connect_timing() {
+        let (_a, a) = listen();
+        let mut connector = BalancingConnector::new(HttpConnector::default(), Strategy::RoundRobin);
+        connector.add_service("service", vec![a]);
+
+        let (stream, timing) = connector.connect_timed("service", 80, "http").unwrap();
+        assert_eq!(stream.backend_addr(), Some(a));
+        assert!(timing.connect.is_some());
+    }
+
+    #[test]
+    fn test_least_outstanding() {
+        let (_a, a) = listen();
+        let (_b, b) = listen();
+        let mut connector = BalancingConnector::new(HttpConnector::default(),
+                                                    Strategy::LeastOutstanding);
+        connector.add_service("service", vec![a, b]);
+
+        let first = connector.connect("service", 80, "http").unwrap();
+        for _ in 0..3 {
+            let other = connector.connect("service", 80, "http").unwrap();
+            assert!(other.backend_addr() != first.backend_addr());
+        }
+    }
+
+    #[test]
+    fn test_ejection() {
+        let (_a, a) = listen();
+        let b = dead();
+        for &strategy in &[Strategy::RoundRobin, Strategy::RandomTwoChoices] {
+            let mut connector = BalancingConnector::new(HttpConnector::default(), strategy);
+            connector.set_ejection(1, Duration::from_secs(60));
+            connector.add_service("service", vec![a, b]);
+
+            // the dead address is failed over, and then left out
+            for _ in 0..4 {
+                let stream = connector.connect("service", 80, "http").unwrap();
+                assert_eq!(stream.backend_addr(), Some(a));
+            }
+        }
+    }
+
+    #[test]
+    fn test_connection_failures() {
+        use std::io::ErrorKind::*;
+        for &kind in &[ConnectionReset, ConnectionAborted, BrokenPipe, UnexpectedEof] {
+            assert!(is_connection_failure(&io::Error::new(kind, "failed")));
+        }
+        for &kind in &[TimedOut, WouldBlock, Interrupted] {
+            assert!(!is_connection_failure(&io::Error::new(kind, "failed")));
+        }
+        assert!(!is_connection_failure(&io::Error::new(Other, "failed")));
+    }
+}
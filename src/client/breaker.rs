@@ -0,0 +1,299 @@
+//! Client Circuit Breaking
+//!
+//! A `CircuitBreaker` set on a `Client` stops it from sending requests to a
+//! host that keeps failing, so that callers fail fast instead of piling up
+//! behind connect timeouts.
+//!
+//! Each host, port and scheme has its own circuit. It starts closed, and
+//! opens after a number of consecutive failures, which are errors sending a
+//! request, or responses with one of the failure statuses. While open,
+//! requests fail with `Error::CircuitOpen`. After the cooldown, it is half
+//! open: a single request is let through to probe the host, and its outcome
+//! closes the circuit again, or reopens it.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use hyper::Client;
+//! use hyper::client::breaker::CircuitBreaker;
+//!
+//! let mut client = Client::new();
+//! client.set_circuit_breaker(Some(CircuitBreaker::new(5, Duration::from_secs(30))));
+//! ```
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use time::precise_time_ns;
+
+use status::StatusCode;
+use super::{Key, key};
+
+/// The state of the circuit of a host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent.
+    Closed,
+    /// Requests fail fast.
+    Open,
+    /// A request probing the host is in flight, and others fail fast.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum Circuit {
+    Closed(u32),
+    Open(u64),
+    HalfOpen,
+}
+
+/// Per host circuit breaking for a `Client`.
+///
+/// Clones of a `CircuitBreaker` share the same circuits.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    failure_statuses: Vec<StatusCode>,
+    circuits: Arc<Mutex<HashMap<Key, Circuit>>>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker that opens a circuit after `threshold` consecutive
+    /// failures, for `cooldown`.
+    ///
+    /// The failure statuses are `502 Bad Gateway`, `503 Service Unavailable`
+    /// and `504 Gateway Timeout`.
+    ///
+    /// # Panics
+    ///
+    /// If `threshold` is 0.
+    pub fn new(threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        assert!(threshold != 0, "Can't open a circuit after 0 failures.");
+        CircuitBreaker {
+            threshold: threshold,
+            cooldown: cooldown,
+            failure_statuses: vec![
+                StatusCode::BadGateway,
+                StatusCode::ServiceUnavailable,
+                StatusCode::GatewayTimeout,
+            ],
+            circuits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Set the response statuses that count as failures.
+    pub fn set_failure_statuses(&mut self, statuses: Vec<StatusCode>) {
+        self.failure_statuses = statuses;
+    }
+
+    /// Get the state of the circuit of a host.
+    pub fn state(&self, host: &str, port: u16, scheme: &str) -> CircuitState {
+        let circuits = self.circuits.lock().unwrap();
+        match circuits.get(&key(host, port, scheme)) {
+            Some(&Circuit::Open(until)) if precise_time_ns() < until => CircuitState::Open,
+            Some(&Circuit::HalfOpen) => CircuitState::HalfOpen,
+            // a cooled down circuit lets the next request probe
+            _ => CircuitState::Closed,
+        }
+    }
+
+    /// Start an attempt to send a request to a host.
+    ///
+    /// Fails with `Error::CircuitOpen` if the circuit of the host is open.
+    /// Otherwise, the outcome of the request must be reported to the returned
+    /// `Attempt`, and dropping it without doing so counts as a failure.
+    pub fn attempt(&self, host: &str, port: u16, scheme: &str) -> ::Result<Attempt> {
+        let key = key(host, port, scheme);
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(key.clone()).or_insert(Circuit::Closed(0));
+        let probe = match *circuit {
+            Circuit::Closed(_) => false,
+            Circuit::Open(until) if precise_time_ns() >= until => true,
+            Circuit::Open(_) | Circuit::HalfOpen => {
+                debug!("circuit for {:?} is open", key);
+                return Err(::Error::CircuitOpen);
+            }
+        };
+        if probe {
+            debug!("circuit for {:?} is half open", key);
+            *circuit = Circuit::HalfOpen;
+        }
+        Ok(Attempt {
+            breaker: self.clone(),
+            key: Some(key),
+            probe: probe,
+        })
+    }
+
+    fn record(&self, key: Key, probe: bool, success: bool) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(key.clone()).or_insert(Circuit::Closed(0));
+        let failures = match (&*circuit, probe) {
+            (&Circuit::Closed(_), false) if success => 0,
+            (&Circuit::Closed(failures), false) => failures + 1,
+            (&Circuit::HalfOpen, true) if success => 0,
+            // a failed probe
+            (&Circuit::HalfOpen, true) => self.threshold,
+            // only the probe decides an open circuit, not attempts that
+            // started before it opened and finish late
+            _ => return,
+        };
+        if failures >= self.threshold && !success {
+            debug!("circuit for {:?} opened after {} failures", key, failures);
+            let cooldown = self.cooldown.as_secs() * 1_000_000_000 +
+                self.cooldown.subsec_nanos() as u64;
+            *circuit = Circuit::Open(precise_time_ns() + cooldown);
+        } else {
+            *circuit = Circuit::Closed(failures);
+        }
+    }
+
+    fn cancel(&self, key: Key, probe: bool) {
+        if !probe {
+            return;
+        }
+        let mut circuits = self.circuits.lock().unwrap();
+        if let Some(circuit) = circuits.get_mut(&key) {
+            // the next request probes instead
+            if let Circuit::HalfOpen = *circuit {
+                *circuit = Circuit::Open(precise_time_ns());
+            }
+        }
+    }
+}
+
+/// An attempt to send a request, started with `CircuitBreaker::attempt`.
+#[derive(Debug)]
+pub struct Attempt {
+    breaker: CircuitBreaker,
+    key: Option<Key>,
+    probe: bool,
+}
+
+impl Attempt {
+    /// Report that the request succeeded.
+    pub fn success(mut self) {
+        self.report(true);
+    }
+
+    /// Report that the request failed.
+    pub fn failure(mut self) {
+        self.report(false);
+    }
+
+    /// Report the status of the response, which succeeded unless it is one of
+    /// the failure statuses.
+    pub fn finish(mut self, status: StatusCode) {
+        let success = !self.breaker.failure_statuses.contains(&status);
+        self.report(success);
+    }
+
+    /// Report that the request wasn't sent, because of an error of the
+    /// caller rather than of the host. It counts neither way.
+    pub fn cancel(mut self) {
+        if let Some(key) = self.key.take() {
+            self.breaker.cancel(key, self.probe);
+        }
+    }
+
+    fn report(&mut self, success: bool) {
+        if let Some(key) = self.key.take() {
+            self.breaker.record(key, self.probe, success);
+        }
+    }
+}
+
+impl Drop for Attempt {
+    fn drop(&mut self) {
+        self.report(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use status::StatusCode;
+    use Error;
+    use super::{CircuitBreaker, CircuitState};
+
+    fn is_open(breaker: &CircuitBreaker) -> bool {
+        match breaker.attempt("127.0.0.1", 80, "http") {
+            Err(Error::CircuitOpen) => true,
+            _ => false
+        }
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().failure();
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().success();
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().finish(StatusCode::ServiceUnavailable);
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Closed);
+        // dropped without an outcome
+        drop(breaker.attempt("127.0.0.1", 80, "http").unwrap());
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Open);
+        assert!(is_open(&breaker));
+        assert_eq!(breaker.state("127.0.0.1", 443, "https"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().failure();
+        assert!(is_open(&breaker));
+        thread::sleep(Duration::from_millis(20));
+
+        let probe = breaker.attempt("127.0.0.1", 80, "http").unwrap();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::HalfOpen);
+        assert!(is_open(&breaker));
+        probe.finish(StatusCode::BadGateway);
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Open);
+        thread::sleep(Duration::from_millis(20));
+
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().finish(StatusCode::Ok);
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_late_outcomes_ignored_while_open() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+        let slow = breaker.attempt("127.0.0.1", 80, "http").unwrap();
+        let late = breaker.attempt("127.0.0.1", 80, "http").unwrap();
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().failure();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Open);
+        slow.success();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Open);
+        thread::sleep(Duration::from_millis(20));
+
+        let probe = breaker.attempt("127.0.0.1", 80, "http").unwrap();
+        late.success();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::HalfOpen);
+        probe.failure();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().cancel();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Closed);
+
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().failure();
+        thread::sleep(Duration::from_millis(20));
+        breaker.attempt("127.0.0.1", 80, "http").unwrap().cancel();
+        // the next request probes instead
+        let probe = breaker.attempt("127.0.0.1", 80, "http").unwrap();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::HalfOpen);
+        probe.success();
+        assert_eq!(breaker.state("127.0.0.1", 80, "http"), CircuitState::Closed);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_threshold() {
+        CircuitBreaker::new(0, Duration::from_secs(60));
+    }
+}
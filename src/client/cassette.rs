@@ -0,0 +1,553 @@
+//! Record and Replay of HTTP Exchanges
+//!
+//! A `CassetteConnector` in record mode sends requests through a real
+//! connector, and saves every exchange to a cassette file. In replay mode,
+//! it serves the saved responses from that file without any network access,
+//! so tests of code using a `Client` can run deterministically, and offline.
+//!
+//! Exchanges are matched by method, URL and body. A request that isn't in the
+//! cassette fails with an error naming it.
+//!
+//! ```no_run
+//! use hyper::Client;
+//! use hyper::client::cassette::CassetteConnector;
+//! use hyper::net::HttpConnector;
+//!
+//! // record once...
+//! let connector = CassetteConnector::record(HttpConnector::default(), "tests/example.json");
+//! Client::with_connector(connector).get("http://example.domain").send().unwrap();
+//!
+//! // ...and replay from then on
+//! let connector = CassetteConnector::replay("tests/example.json").unwrap();
+//! Client::with_connector(connector).get("http://example.domain").send().unwrap();
+//! ```
+//!
+//! While recording, an exchange is saved once its connection is used for
+//! the next request, or closed. Dropping the `Client` closes all of them.
+//!
+//! A `100 Continue` answering a request with `Expect: 100-continue` is saved
+//! too, and replayed before the body is written.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Write};
+use std::mem;
+use std::net::{SocketAddr, Shutdown};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "timeouts")]
+use std::time::Duration;
+
+use httparse;
+use serialize::base64::{FromBase64, ToBase64, STANDARD};
+use serialize::json::Json;
+
+use header::{ContentLength, Encoding, Expect, Headers, TransferEncoding};
+use http::h1::TrailersReader;
+use http::h1::HttpReader::{SizedReader, ChunkedReader, EmptyReader};
+use net::{ConnectTiming, HttpConnector, NetworkConnector, NetworkStream, StreamCloser, TlsInfo};
+
+/// A `NetworkConnector` that records exchanges to a cassette file, or
+/// replays them from one.
+pub struct CassetteConnector<C = HttpConnector> {
+    // `None` when replaying
+    connector: Option<C>,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl<C: NetworkConnector> CassetteConnector<C> {
+    /// Create a connector that sends requests through `connector`, and saves
+    /// every exchange to the cassette file at `path`, replacing it.
+    pub fn record<P: AsRef<Path>>(connector: C, path: P) -> CassetteConnector<C> {
+        CassetteConnector {
+            connector: Some(connector),
+            cassette: Arc::new(Mutex::new(Cassette {
+                path: path.as_ref().to_owned(),
+                interactions: Vec::new(),
+            })),
+        }
+    }
+}
+
+impl CassetteConnector {
+    /// Create a connector that serves the responses saved in the cassette
+    /// file at `path`.
+    pub fn replay<P: AsRef<Path>>(path: P) -> ::Result<CassetteConnector> {
+        let cassette = try!(Cassette::load(path.as_ref()));
+        Ok(CassetteConnector {
+            connector: None,
+            cassette: Arc::new(Mutex::new(cassette)),
+        })
+    }
+}
+
+impl<C: NetworkConnector> NetworkConnector for CassetteConnector<C> {
+    type Stream = CassetteStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<CassetteStream> {
+        let inner = match self.connector {
+            Some(ref connector) => Some(try!(connector.connect(host, port, scheme)).into()),
+            None => None
+        };
+        Ok(CassetteStream {
+            inner: inner,
+            origin: format!("{}://{}:{}", scheme, host, port),
+            cassette: self.cassette.clone(),
+            request: Vec::new(),
+            response: Vec::new(),
+            pos: 0,
+            continued: false,
+        })
+    }
+}
+
+/// A connection of a `CassetteConnector`.
+pub struct CassetteStream {
+    // the real stream, when recording
+    inner: Option<Box<NetworkStream + Send>>,
+    origin: String,
+    cassette: Arc<Mutex<Cassette>>,
+    request: Vec<u8>,
+    response: Vec<u8>,
+    // how much of `response` has been replayed
+    pos: usize,
+    // whether a `100 Continue` was replayed for the request being written
+    continued: bool,
+}
+
+impl CassetteStream {
+    fn url(&self, target: &str) -> String {
+        if target.starts_with('/') {
+            format!("{}{}", self.origin, target)
+        } else {
+            // absolute-form, sent to a proxy
+            target.to_owned()
+        }
+    }
+
+    fn save_exchange(&mut self) {
+        let request = mem::replace(&mut self.request, Vec::new());
+        let mut response = mem::replace(&mut self.response, Vec::new());
+        let continued = match continue_len(&response) {
+            Some(len) => {
+                response = response[len..].to_vec();
+                true
+            },
+            None => false
+        };
+        let (method, target, body) = match parse_request(&request) {
+            Ok(Some((method, target, body, _))) => (method, target, body),
+            Ok(None) => return debug!("not recording incomplete request"),
+            Err(e) => return debug!("not recording invalid request: {}", e)
+        };
+        let url = self.url(&target);
+        debug!("recording {} {}", method, url);
+        let mut cassette = self.cassette.lock().unwrap();
+        cassette.interactions.push(Interaction {
+            method: method,
+            url: url,
+            body: body,
+            response: response,
+            continued: continued,
+            replayed: false,
+        });
+        if let Err(e) = cassette.save() {
+            error!("error saving cassette {:?}: {}", cassette.path, e);
+        }
+    }
+
+    fn replay_next(&mut self) -> io::Result<()> {
+        if self.request.is_empty() {
+            // nothing was asked, so this reads as EOF
+            return Ok(());
+        }
+        let (method, target, body, len) = match try!(parse_request(&self.request)) {
+            Some(request) => request,
+            None => return self.replay_continue()
+        };
+        self.request = self.request[len..].to_vec();
+        let url = self.url(&target);
+        self.response = try!(self.cassette.lock().unwrap().replay(&method, &url, &body));
+        self.pos = 0;
+        self.continued = false;
+        Ok(())
+    }
+
+    // The body of a request expecting `100 Continue` is held back until the
+    // server answers, so the `100 Continue` is replayed if one was recorded.
+    fn replay_continue(&mut self) -> io::Result<()> {
+        let (method, target, headers, _) = match try!(parse_head(&self.request)) {
+            Some(head) => head,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "reading before the request head was written"))
+        };
+        let url = self.url(&target);
+        let expects = headers.get::<Expect>() == Some(&Expect::Continue);
+        if expects && !self.continued && self.cassette.lock().unwrap().continues(&method, &url) {
+            debug!("replaying 100 Continue for {} {}", method, url);
+            self.response = CONTINUE.to_vec();
+            self.pos = 0;
+            self.continued = true;
+            Ok(())
+        } else {
+            // the server didn't answer until the client gave up waiting
+            Err(io::Error::new(io::ErrorKind::TimedOut,
+                               format!("no recorded 100 Continue for {} {}", method, url)))
+        }
+    }
+}
+
+impl Read for CassetteStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(ref mut inner) = self.inner {
+            let n = try!(inner.read(buf));
+            self.response.extend(buf[..n].iter().cloned());
+            return Ok(n);
+        }
+        if self.pos == self.response.len() {
+            try!(self.replay_next());
+        }
+        let n = try!((&self.response[self.pos..]).read(buf));
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for CassetteStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.inner.is_some() && !self.response.is_empty() &&
+                parse_request(&self.request).ok().map_or(false, |req| req.is_some()) {
+            // a new request, so the last exchange is over, rather than the
+            // body of one that was answered with `100 Continue`
+            self.save_exchange();
+        }
+        let n = match self.inner {
+            Some(ref mut inner) => try!(inner.write(buf)),
+            None => buf.len()
+        };
+        self.request.extend(buf[..n].iter().cloned());
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner {
+            Some(ref mut inner) => inner.flush(),
+            None => Ok(())
+        }
+    }
+}
+
+impl NetworkStream for CassetteStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        match self.inner {
+            Some(ref mut inner) => inner.peer_addr(),
+            None => Ok("0.0.0.0:0".parse().unwrap())
+        }
+    }
+
+    #[cfg(feature = "timeouts")]
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        match self.inner {
+            Some(ref inner) => inner.set_read_timeout(dur),
+            None => Ok(())
+        }
+    }
+
+    #[cfg(feature = "timeouts")]
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        match self.inner {
+            Some(ref inner) => inner.set_write_timeout(dur),
+            None => Ok(())
+        }
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        match self.inner {
+            Some(ref mut inner) => inner.close(how),
+            None => Ok(())
+        }
+    }
+
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        self.inner.as_ref().and_then(|inner| inner.closer())
+    }
+
+    fn tls_info(&self) -> Option<TlsInfo> {
+        self.inner.as_ref().and_then(|inner| inner.tls_info())
+    }
+
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        self.inner.as_ref().and_then(|inner| inner.connect_timing())
+    }
+}
+
+impl Drop for CassetteStream {
+    fn drop(&mut self) {
+        if self.inner.is_some() && !self.request.is_empty() {
+            self.save_exchange();
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Cassette {
+    path: PathBuf,
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug)]
+struct Interaction {
+    method: String,
+    url: String,
+    body: Vec<u8>,
+    response: Vec<u8>,
+    // whether a `100 Continue` came before `response`
+    continued: bool,
+    replayed: bool,
+}
+
+impl Cassette {
+    fn load(path: &Path) -> ::Result<Cassette> {
+        let mut s = String::new();
+        try!(try!(File::open(path)).read_to_string(&mut s));
+        let json = try!(Json::from_str(&s).map_err(|e| invalid(&e.to_string())));
+        let list = match json.find("interactions").and_then(|list| list.as_array()) {
+            Some(list) => list,
+            None => return Err(invalid("missing interactions").into())
+        };
+        let mut interactions = Vec::with_capacity(list.len());
+        for obj in list {
+            interactions.push(Interaction {
+                method: try!(string(obj, "method")).to_owned(),
+                url: try!(string(obj, "url")).to_owned(),
+                body: try!(bytes(obj, "body")),
+                response: try!(bytes(obj, "response")),
+                continued: obj.find("continue").and_then(|c| c.as_boolean()).unwrap_or(false),
+                replayed: false,
+            });
+        }
+        Ok(Cassette {
+            path: path.to_owned(),
+            interactions: interactions,
+        })
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let interactions = self.interactions.iter().map(|interaction| {
+            let mut obj = BTreeMap::new();
+            obj.insert("method".to_owned(), Json::String(interaction.method.clone()));
+            obj.insert("url".to_owned(), Json::String(interaction.url.clone()));
+            insert_bytes(&mut obj, "body", &interaction.body);
+            insert_bytes(&mut obj, "response", &interaction.response);
+            if interaction.continued {
+                obj.insert("continue".to_owned(), Json::Boolean(true));
+            }
+            Json::Object(obj)
+        }).collect();
+        let mut cassette = BTreeMap::new();
+        cassette.insert("interactions".to_owned(), Json::Array(interactions));
+        let mut file = try!(File::create(&self.path));
+        write!(file, "{}", Json::Object(cassette).pretty())
+    }
+
+    // Whether a request to `url` was answered with `100 Continue`.
+    fn continues(&self, method: &str, url: &str) -> bool {
+        self.interactions.iter().any(|interaction| {
+            interaction.method == method && interaction.url == url && interaction.continued
+        })
+    }
+
+    // Identical requests are answered in the order they were recorded, and
+    // the last answer is repeated after that.
+    fn replay(&mut self, method: &str, url: &str, body: &[u8]) -> io::Result<Vec<u8>> {
+        let mut found = None;
+        for (i, interaction) in self.interactions.iter().enumerate() {
+            if interaction.method == method && interaction.url == url && interaction.body == body {
+                found = Some(i);
+                if !interaction.replayed {
+                    break;
+                }
+            }
+        }
+        match found {
+            Some(i) => {
+                debug!("replaying {} {}", method, url);
+                self.interactions[i].replayed = true;
+                Ok(self.interactions[i].response.clone())
+            },
+            None => {
+                let msg = format!("no recorded response in {:?} for {} {} with a {} byte body",
+                                  self.path, method, url, body.len());
+                error!("{}", msg);
+                Err(io::Error::new(io::ErrorKind::Other, msg))
+            }
+        }
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("invalid cassette: {}", msg))
+}
+
+fn string<'a>(obj: &'a Json, key: &str) -> io::Result<&'a str> {
+    obj.find(key).and_then(|value| value.as_string()).ok_or_else(|| {
+        invalid(&format!("missing {}", key))
+    })
+}
+
+// Bytes are saved as text when they are UTF-8, and base64 otherwise.
+fn insert_bytes(obj: &mut BTreeMap<String, Json>, key: &str, bytes: &[u8]) {
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(text) => {
+            obj.insert(key.to_owned(), Json::String(text));
+        },
+        Err(_) => {
+            obj.insert(key.to_owned(), Json::String(bytes.to_base64(STANDARD)));
+            obj.insert(format!("{}_encoding", key), Json::String("base64".to_owned()));
+        }
+    }
+}
+
+fn bytes(obj: &Json, key: &str) -> io::Result<Vec<u8>> {
+    let value = try!(string(obj, key));
+    match obj.find(&format!("{}_encoding", key)).and_then(|enc| enc.as_string()) {
+        Some("base64") => value.from_base64().map_err(|e| invalid(&e.to_string())),
+        Some(enc) => Err(invalid(&format!("unknown encoding {}", enc))),
+        None => Ok(value.as_bytes().to_vec())
+    }
+}
+
+const CONTINUE: &'static [u8] = b"HTTP/1.1 100 Continue\r\n\r\n";
+
+// The length of a `100 Continue` at the start of a recorded response, if any.
+fn continue_len(buf: &[u8]) -> Option<usize> {
+    let mut raw = [httparse::EMPTY_HEADER; 100];
+    let mut res = httparse::Response::new(&mut raw);
+    match res.parse(buf) {
+        Ok(httparse::Status::Complete(len)) if res.code == Some(100) => Some(len),
+        _ => None
+    }
+}
+
+// Parses the method, request-target and headers of the first request in
+// `buf`, and how many bytes the head took. Returns `None` if it's incomplete.
+fn parse_head(buf: &[u8]) -> io::Result<Option<(String, String, Headers, usize)>> {
+    let mut raw = [httparse::EMPTY_HEADER; 100];
+    let mut req = httparse::Request::new(&mut raw);
+    let head_len = match req.parse(buf) {
+        Ok(httparse::Status::Complete(len)) => len,
+        Ok(httparse::Status::Partial) => return Ok(None),
+        Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                            format!("invalid request: {:?}", e)))
+    };
+    let method = req.method.unwrap_or("").to_owned();
+    let target = req.path.unwrap_or("").to_owned();
+    let headers = try!(Headers::from_raw(req.headers).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }));
+    Ok(Some((method, target, headers, head_len)))
+}
+
+// Parses the method, request-target and decoded body of the first request
+// in `buf`, and how many bytes it took. Returns `None` if it's incomplete.
+fn parse_request(buf: &[u8]) -> io::Result<Option<(String, String, Vec<u8>, usize)>> {
+    let (method, target, headers, head_len) = match try!(parse_head(buf)) {
+        Some(head) => head,
+        None => return Ok(None)
+    };
+
+    let chunked = headers.get::<TransferEncoding>()
+        .map_or(false, |encodings| encodings.contains(&Encoding::Chunked));
+    let mut rest = Cursor::new(&buf[head_len..]);
+    let mut body = Vec::new();
+    let read = {
+        let mut reader = TrailersReader::new(if chunked {
+            ChunkedReader(&mut rest, None)
+        } else {
+            match headers.get::<ContentLength>() {
+                Some(&ContentLength(len)) => SizedReader(&mut rest, len),
+                None => EmptyReader(&mut rest)
+            }
+        });
+        reader.read_to_end(&mut body)
+    };
+    match read {
+        Ok(_) => Ok(Some((method, target, body, head_len + rest.position() as usize))),
+        // the rest of the body hasn't been written yet
+        Err(_) => Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use client::Client;
+    use net::NetworkConnector;
+    use testing::TempPath;
+    use super::CassetteConnector;
+
+    mock_connector!(MockOrigin {
+        "http://127.0.0.1" => "HTTP/1.1 200 OK\r\n\
+                               Content-Length: 5\r\n\
+                               \r\n\
+                               hello"
+    });
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = TempPath::new("hyper-test-cassette");
+        {
+            let client = Client::with_connector(CassetteConnector::record(MockOrigin, &path));
+            let mut s = String::new();
+            client.post("http://127.0.0.1/echo").body("ping")
+                .send().unwrap().read_to_string(&mut s).unwrap();
+            assert_eq!(s, "hello");
+        }
+
+        let client = Client::with_connector(CassetteConnector::replay(&path).unwrap());
+        let mut s = String::new();
+        client.post("http://127.0.0.1/echo").body("ping")
+            .send().unwrap().read_to_string(&mut s).unwrap();
+        assert_eq!(s, "hello");
+
+        assert!(client.post("http://127.0.0.1/echo").body("pong").send().is_err());
+        assert!(client.get("http://127.0.0.1/other").send().is_err());
+    }
+
+    mock_connector!(MockContinue {
+        "http://127.0.0.1" => "HTTP/1.1 100 Continue\r\n\
+                               \r\n\
+                               HTTP/1.1 200 OK\r\n\
+                               Content-Length: 5\r\n\
+                               \r\n\
+                               hello"
+    });
+
+    #[test]
+    fn test_record_and_replay_continue() {
+        let path = TempPath::new("hyper-test-cassette-continue");
+        let head = b"POST /upload HTTP/1.1\r\n\
+                     Host: 127.0.0.1\r\n\
+                     Expect: 100-continue\r\n\
+                     Content-Length: 4\r\n\
+                     \r\n";
+        {
+            let connector = CassetteConnector::record(MockContinue, &path);
+            let mut stream = connector.connect("127.0.0.1", 80, "http").unwrap();
+            stream.write_all(head).unwrap();
+            let mut buf = [0; 256];
+            stream.read(&mut buf).unwrap();
+            stream.write_all(b"ping").unwrap();
+        }
+
+        let connector = CassetteConnector::replay(&path).unwrap();
+        let mut stream = connector.connect("127.0.0.1", 80, "http").unwrap();
+        stream.write_all(head).unwrap();
+        let mut buf = [0; 256];
+        let n = stream.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"HTTP/1.1 100 Continue\r\n\r\n");
+        stream.write_all(b"ping").unwrap();
+        let n = stream.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], &b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello"[..]);
+    }
+}
@@ -0,0 +1,333 @@
+//! HTTP Archive Recording
+//!
+//! A `HarRecorder` set on a `Client` records every request and response it
+//! sends into an HTTP Archive (HAR 1.2) log, which can be written as JSON,
+//! and opened by browser developer tools and other HAR viewers.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use hyper::Client;
+//! use hyper::client::har::HarRecorder;
+//!
+//! let recorder = HarRecorder::with_body_limit(64 * 1024);
+//! let mut client = Client::new();
+//! client.set_har_recorder(Some(recorder.clone()));
+//!
+//! client.get("http://example.domain").send().unwrap();
+//!
+//! let mut file = File::create("example.har").unwrap();
+//! recorder.write_json(&mut file).unwrap();
+//! ```
+//!
+//! An exchange is recorded once its `Response` is dropped, so that the
+//! whole body and timing are known.
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serialize::base64::{ToBase64, STANDARD};
+use serialize::json::{Json, ToJson};
+use time::{self, Tm};
+use url::Url;
+
+use client::response::Timing;
+use header::{ContentType, Headers};
+use method::Method;
+use version::HttpVersion;
+
+/// A thread-safe log of HTTP exchanges, in the HAR 1.2 format.
+///
+/// Clones of a `HarRecorder` share the same log.
+#[derive(Clone, Debug)]
+pub struct HarRecorder {
+    entries: Arc<Mutex<Vec<HarEntry>>>,
+    body_limit: Option<usize>,
+}
+
+impl HarRecorder {
+    /// Create a recorder that leaves out request and response bodies.
+    pub fn new() -> HarRecorder {
+        HarRecorder {
+            entries: Arc::new(Mutex::new(Vec::new())),
+            body_limit: None,
+        }
+    }
+
+    /// Create a recorder that keeps up to `limit` bytes of each request and
+    /// response body.
+    ///
+    /// Longer bodies are truncated, though their full size is recorded.
+    pub fn with_body_limit(limit: usize) -> HarRecorder {
+        HarRecorder {
+            entries: Arc::new(Mutex::new(Vec::new())),
+            body_limit: Some(limit),
+        }
+    }
+
+    /// How many bytes of each body are kept, if any.
+    pub fn body_limit(&self) -> Option<usize> {
+        self.body_limit
+    }
+
+    /// Add an entry to the log.
+    pub fn record(&self, entry: HarEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// Get a copy of the entries recorded so far.
+    pub fn entries(&self) -> Vec<HarEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Remove all entries from the log.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Write the log as HAR JSON.
+    pub fn write_json<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "{}", self.to_json())
+    }
+}
+
+impl Default for HarRecorder {
+    fn default() -> HarRecorder {
+        HarRecorder::new()
+    }
+}
+
+impl ToJson for HarRecorder {
+    fn to_json(&self) -> Json {
+        let entries = self.entries.lock().unwrap();
+        let mut creator = BTreeMap::new();
+        creator.insert("name".to_owned(), "hyper".to_json());
+        creator.insert("version".to_owned(), env!("CARGO_PKG_VERSION").to_json());
+
+        let mut log = BTreeMap::new();
+        log.insert("version".to_owned(), "1.2".to_json());
+        log.insert("creator".to_owned(), Json::Object(creator));
+        log.insert("entries".to_owned(), entries.to_json());
+
+        let mut har = BTreeMap::new();
+        har.insert("log".to_owned(), Json::Object(log));
+        Json::Object(har)
+    }
+}
+
+/// A single request and response, as recorded by a `HarRecorder`.
+#[derive(Clone, Debug)]
+pub struct HarEntry {
+    /// When the request was started.
+    pub started: Tm,
+    /// The method of the request.
+    pub method: Method,
+    /// The URL of the request.
+    pub url: Url,
+    /// The HTTP version of the request.
+    pub request_version: HttpVersion,
+    /// The headers of the request.
+    pub request_headers: Headers,
+    /// The start of the request body, if bodies are recorded.
+    pub request_body: Option<Vec<u8>>,
+    /// The full size of the request body that was sent.
+    pub request_body_size: u64,
+    /// The status code of the response.
+    pub status: u16,
+    /// The reason phrase of the response.
+    pub reason: String,
+    /// The HTTP version of the response.
+    pub version: HttpVersion,
+    /// The headers of the response.
+    pub response_headers: Headers,
+    /// The start of the response body, if bodies are recorded.
+    pub response_body: Option<Vec<u8>>,
+    /// The size of the response body that was read.
+    pub response_body_size: u64,
+    /// How long each phase of the exchange took.
+    pub timing: Timing,
+}
+
+impl HarEntry {
+    /// Start an entry for a request, without a response yet.
+    pub fn new(method: Method, url: Url, headers: Headers) -> HarEntry {
+        HarEntry {
+            started: time::now_utc(),
+            method: method,
+            url: url,
+            request_version: HttpVersion::Http11,
+            request_headers: headers,
+            request_body: None,
+            request_body_size: 0,
+            status: 0,
+            reason: String::new(),
+            version: HttpVersion::Http11,
+            response_headers: Headers::new(),
+            response_body: None,
+            response_body_size: 0,
+            timing: Timing::default(),
+        }
+    }
+}
+
+impl ToJson for HarEntry {
+    fn to_json(&self) -> Json {
+        let mut request = BTreeMap::new();
+        request.insert("method".to_owned(), self.method.to_string().to_json());
+        request.insert("url".to_owned(), self.url.serialize().to_json());
+        request.insert("httpVersion".to_owned(), self.request_version.to_string().to_json());
+        request.insert("cookies".to_owned(), Json::Array(Vec::new()));
+        request.insert("headers".to_owned(), headers_json(&self.request_headers));
+        let query = self.url.query_pairs().unwrap_or(Vec::new());
+        request.insert("queryString".to_owned(), Json::Array(query.into_iter().map(|(name, value)| {
+            name_value(name, value)
+        }).collect()));
+        if let Some(ref body) = self.request_body {
+            let mut post_data = content_json(&self.request_headers, Some(&body[..]));
+            post_data.insert("params".to_owned(), Json::Array(Vec::new()));
+            request.insert("postData".to_owned(), Json::Object(post_data));
+        }
+        request.insert("headersSize".to_owned(), Json::I64(-1));
+        request.insert("bodySize".to_owned(), Json::U64(self.request_body_size));
+
+        let body = self.response_body.as_ref().map(|body| &body[..]);
+        let mut content = content_json(&self.response_headers, body);
+        content.insert("size".to_owned(), Json::U64(self.response_body_size));
+
+        let mut response = BTreeMap::new();
+        response.insert("status".to_owned(), Json::U64(self.status as u64));
+        response.insert("statusText".to_owned(), self.reason.to_json());
+        response.insert("httpVersion".to_owned(), self.version.to_string().to_json());
+        response.insert("cookies".to_owned(), Json::Array(Vec::new()));
+        response.insert("headers".to_owned(), headers_json(&self.response_headers));
+        response.insert("content".to_owned(), Json::Object(content));
+        let location = self.response_headers.get_raw("Location")
+            .and_then(|raw| raw.first())
+            .and_then(|raw| str::from_utf8(raw).ok())
+            .unwrap_or("");
+        response.insert("redirectURL".to_owned(), location.to_json());
+        response.insert("headersSize".to_owned(), Json::I64(-1));
+        response.insert("bodySize".to_owned(), Json::U64(self.response_body_size));
+
+        let timing = &self.timing;
+        let before_wait = [timing.pool, timing.dns, timing.connect, timing.tls, timing.write]
+            .iter()
+            .fold(0.0, |sum, phase| sum + phase.map_or(0.0, millis));
+        let ttfb = timing.ttfb.map_or(before_wait, millis);
+        let total = timing.total.map_or(ttfb, millis);
+        let mut timings = BTreeMap::new();
+        timings.insert("blocked".to_owned(), optional_millis(timing.pool));
+        timings.insert("dns".to_owned(), optional_millis(timing.dns));
+        // HAR counts the TLS handshake as part of connecting
+        let connect = match (timing.connect, timing.tls) {
+            (Some(connect), Some(tls)) => Json::F64(millis(connect) + millis(tls)),
+            (connect, _) => optional_millis(connect)
+        };
+        timings.insert("connect".to_owned(), connect);
+        timings.insert("ssl".to_owned(), optional_millis(timing.tls));
+        timings.insert("send".to_owned(), Json::F64(timing.write.map_or(0.0, millis)));
+        timings.insert("wait".to_owned(), Json::F64((ttfb - before_wait).max(0.0)));
+        timings.insert("receive".to_owned(), Json::F64((total - ttfb).max(0.0)));
+
+        let mut entry = BTreeMap::new();
+        entry.insert("startedDateTime".to_owned(), self.started.rfc3339().to_string().to_json());
+        entry.insert("time".to_owned(), Json::F64(total));
+        entry.insert("request".to_owned(), Json::Object(request));
+        entry.insert("response".to_owned(), Json::Object(response));
+        entry.insert("cache".to_owned(), Json::Object(BTreeMap::new()));
+        entry.insert("timings".to_owned(), Json::Object(timings));
+        Json::Object(entry)
+    }
+}
+
+fn name_value(name: String, value: String) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("name".to_owned(), Json::String(name));
+    obj.insert("value".to_owned(), Json::String(value));
+    Json::Object(obj)
+}
+
+fn headers_json(headers: &Headers) -> Json {
+    Json::Array(headers.iter().map(|header| {
+        name_value(header.name().to_owned(), header.value_string())
+    }).collect())
+}
+
+// Bodies that aren't UTF-8 are base64 encoded, as HAR allows.
+fn content_json(headers: &Headers, body: Option<&[u8]>) -> BTreeMap<String, Json> {
+    let mut content = BTreeMap::new();
+    let mime = headers.get::<ContentType>().map_or(String::new(), |ct| ct.0.to_string());
+    content.insert("mimeType".to_owned(), mime.to_json());
+    match body.map(|body| (body, str::from_utf8(body))) {
+        Some((_, Ok(text))) => {
+            content.insert("text".to_owned(), text.to_json());
+        },
+        Some((body, Err(_))) => {
+            content.insert("text".to_owned(), body.to_base64(STANDARD).to_json());
+            content.insert("encoding".to_owned(), "base64".to_json());
+        },
+        None => ()
+    }
+    content
+}
+
+fn millis(dur: Duration) -> f64 {
+    dur.as_secs() as f64 * 1000.0 + dur.subsec_nanos() as f64 / 1_000_000.0
+}
+
+fn optional_millis(dur: Option<Duration>) -> Json {
+    match dur {
+        Some(dur) => Json::F64(millis(dur)),
+        None => Json::I64(-1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serialize::json::{Json, ToJson};
+    use url::Url;
+
+    use header::{Headers, ContentType};
+    use method::Method;
+    use version::HttpVersion;
+    use super::{HarEntry, HarRecorder};
+
+    #[test]
+    fn test_har_json() {
+        let recorder = HarRecorder::with_body_limit(4);
+        let url = Url::parse("http://example.domain/a?b=c").unwrap();
+        let mut entry = HarEntry::new(Method::Get, url, Headers::new());
+        entry.request_version = HttpVersion::Http10;
+        entry.status = 200;
+        entry.reason = "OK".to_owned();
+        entry.response_headers.set(ContentType::plaintext());
+        entry.response_body = Some(b"\xff\x00".to_vec());
+        entry.response_body_size = 2;
+        recorder.record(entry);
+
+        let json = recorder.to_json();
+        let log = json.find("log").unwrap();
+        assert_eq!(log.find("version"), Some(&"1.2".to_json()));
+        let entries = log.find("entries").unwrap().as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let request = entries[0].find("request").unwrap();
+        assert_eq!(request.find("method"), Some(&"GET".to_json()));
+        assert_eq!(request.find("httpVersion"), Some(&"HTTP/1.0".to_json()));
+        assert_eq!(request.find_path(&["queryString"]).unwrap().as_array().unwrap().len(), 1);
+        assert!(request.find("postData").is_none());
+
+        let content = entries[0].find_path(&["response", "content"]).unwrap();
+        assert_eq!(content.find("text"), Some(&"/wA=".to_json()));
+        assert_eq!(content.find("encoding"), Some(&"base64".to_json()));
+        assert_eq!(content.find("size"), Some(&Json::U64(2)));
+
+        let mut out = Vec::new();
+        recorder.write_json(&mut out).unwrap();
+        assert_eq!(Json::from_str(::std::str::from_utf8(&out).unwrap()).unwrap(), json);
+
+        recorder.clear();
+        assert!(recorder.entries().is_empty());
+    }
+}
@@ -0,0 +1,357 @@
+//! Client Rate Limiting
+//!
+//! A `RateLimiter` wraps the `Pool` of a `Client`, and limits how often, and
+//! how many at once, requests are made to each host. Like the pool, it keys
+//! hosts on their host, port and scheme.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use hyper::Client;
+//! use hyper::client::Pool;
+//! use hyper::client::limit::{Limits, RateLimiter};
+//!
+//! let limiter = RateLimiter::new(Pool::new(Default::default()), Limits {
+//!     requests_per_second: Some(10.0),
+//!     burst: 5,
+//!     max_in_flight: Some(2),
+//!     max_wait: Some(Duration::from_secs(30)),
+//! });
+//! let client = Client::with_connector(limiter);
+//! ```
+//!
+//! A request takes its place in flight when its connection is taken from
+//! the pool, and gives it up when its `Response` is dropped.
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, Shutdown};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use time::precise_time_ns;
+
+use client::pool::Pool;
+use net::{ConnectTiming, DefaultConnector, NetworkConnector, NetworkStream, StreamCloser,
+          TlsInfo, ns_to_duration};
+use super::{Key, key};
+
+/// The limits on requests to a host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// How many requests per second may be started, on average.
+    ///
+    /// Must be greater than 0.
+    pub requests_per_second: Option<f64>,
+    /// How many requests may be started at once, after a quiet period,
+    /// above `requests_per_second`.
+    pub burst: u32,
+    /// How many requests may be in flight at once.
+    pub max_in_flight: Option<usize>,
+    /// How long a request may wait for its turn before failing.
+    ///
+    /// If `None`, it waits as long as it takes.
+    pub max_wait: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            requests_per_second: None,
+            burst: 1,
+            max_in_flight: None,
+            max_wait: None,
+        }
+    }
+}
+
+/// A `NetworkConnector` that limits the requests made through another,
+/// per host.
+///
+/// Requests over the limits block until they can be made. If they would wait
+/// longer than `Limits::max_wait`, they fail with a `TimedOut` io error.
+pub struct RateLimiter<C = Pool<DefaultConnector>> {
+    connector: C,
+    limits: Limits,
+    hosts: HashMap<Key, Limits>,
+    state: Arc<LimiterState>,
+}
+
+struct LimiterState {
+    buckets: Mutex<HashMap<Key, Bucket>>,
+    freed: Condvar,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: u64,
+    in_flight: usize,
+}
+
+impl<C: NetworkConnector> RateLimiter<C> {
+    /// Wrap `connector`, limiting every host to `limits`.
+    ///
+    /// # Panics
+    ///
+    /// If `limits.requests_per_second` isn't greater than 0.
+    pub fn new(connector: C, limits: Limits) -> RateLimiter<C> {
+        check_limits(&limits);
+        RateLimiter {
+            connector: connector,
+            limits: limits,
+            hosts: HashMap::new(),
+            state: Arc::new(LimiterState {
+                buckets: Mutex::new(HashMap::new()),
+                freed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Set different limits for one host.
+    ///
+    /// # Panics
+    ///
+    /// If `limits.requests_per_second` isn't greater than 0.
+    pub fn set_host_limits(&mut self, host: &str, port: u16, scheme: &str, limits: Limits) {
+        check_limits(&limits);
+        self.hosts.insert(key(host, port, scheme), limits);
+    }
+
+    // Blocks until a request to `key` is within `limits`, and counts it.
+    fn acquire(&self, key: &Key, limits: &Limits) -> io::Result<()> {
+        let deadline = limits.max_wait.map(|wait| {
+            precise_time_ns() + wait.as_secs() * 1_000_000_000 + wait.subsec_nanos() as u64
+        });
+        let burst = limits.burst as f64;
+        let mut buckets = self.state.buckets.lock().unwrap();
+        loop {
+            let wait_ns = {
+                let bucket = buckets.entry(key.clone()).or_insert(Bucket {
+                    tokens: burst,
+                    refilled: precise_time_ns(),
+                    in_flight: 0,
+                });
+
+                let now = precise_time_ns();
+                let mut wait_ns = None;
+                if let Some(rate) = limits.requests_per_second {
+                    let elapsed = now.saturating_sub(bucket.refilled) as f64 / 1e9;
+                    bucket.tokens = (bucket.tokens + elapsed * rate).min(burst.max(1.0));
+                    bucket.refilled = now;
+                    if bucket.tokens < 1.0 {
+                        wait_ns = Some(((1.0 - bucket.tokens) / rate * 1e9) as u64 + 1);
+                    }
+                }
+                let full = limits.max_in_flight.map_or(false, |max| bucket.in_flight >= max);
+                if wait_ns.is_none() && !full {
+                    if limits.requests_per_second.is_some() {
+                        bucket.tokens -= 1.0;
+                    }
+                    bucket.in_flight += 1;
+                    return Ok(());
+                }
+                // while full, a freed request notifies us
+                wait_ns
+            };
+
+            let now = precise_time_ns();
+            let remaining = deadline.map(|deadline| deadline.saturating_sub(now));
+            if remaining == Some(0) {
+                debug!("rate limit wait for {:?} timed out", key);
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "rate limit wait timed out"));
+            }
+            trace!("rate limit waiting for {:?}", key);
+            buckets = match (wait_ns, remaining) {
+                (None, None) => self.state.freed.wait(buckets).unwrap(),
+                (Some(ns), None) | (None, Some(ns)) => {
+                    self.state.freed.wait_timeout(buckets, ns_to_duration(ns)).unwrap().0
+                },
+                (Some(a), Some(b)) => {
+                    self.state.freed.wait_timeout(buckets, ns_to_duration(a.min(b))).unwrap().0
+                }
+            };
+        }
+    }
+}
+
+fn check_limits(limits: &Limits) {
+    if let Some(rate) = limits.requests_per_second {
+        assert!(rate > 0.0, "requests_per_second must be greater than 0, not {}.", rate);
+    }
+}
+
+impl<C> NetworkConnector for RateLimiter<C>
+where C: NetworkConnector, C::Stream: NetworkStream + Send {
+    type Stream = LimitedStream<C::Stream>;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<LimitedStream<C::Stream>> {
+        let key = key(host, port, scheme);
+        let limits = self.hosts.get(&key).unwrap_or(&self.limits);
+        try!(self.acquire(&key, limits));
+        let permit = Permit {
+            key: key,
+            state: self.state.clone(),
+        };
+        // the permit is given back if connecting fails
+        let stream = try!(self.connector.connect(host, port, scheme));
+        Ok(LimitedStream {
+            inner: stream,
+            _permit: permit,
+        })
+    }
+}
+
+// A request in flight, until dropped.
+struct Permit {
+    key: Key,
+    state: Arc<LimiterState>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(bucket) = self.state.buckets.lock().unwrap().get_mut(&self.key) {
+            bucket.in_flight -= 1;
+        }
+        self.state.freed.notify_all();
+    }
+}
+
+/// A stream of a `RateLimiter`, counted in flight until dropped.
+pub struct LimitedStream<S> {
+    inner: S,
+    _permit: Permit,
+}
+
+impl<S: Read> Read for LimitedStream<S> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<S: Write> Write for LimitedStream<S> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: NetworkStream> NetworkStream for LimitedStream<S> {
+    #[inline]
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    #[cfg(feature = "timeouts")]
+    #[inline]
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(dur)
+    }
+
+    #[cfg(feature = "timeouts")]
+    #[inline]
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_write_timeout(dur)
+    }
+
+    #[inline]
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.inner.close(how)
+    }
+
+    #[inline]
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        self.inner.closer()
+    }
+
+    #[inline]
+    fn is_reused(&self) -> bool {
+        self.inner.is_reused()
+    }
+
+    #[inline]
+    fn tls_info(&self) -> Option<TlsInfo> {
+        self.inner.tls_info()
+    }
+
+    #[inline]
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        self.inner.connect_timing()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    use testing::MockConnector;
+    use net::NetworkConnector;
+    use Error;
+    use super::{Limits, RateLimiter};
+
+    fn connector() -> MockConnector {
+        let mut connector = MockConnector::new();
+        connector.add_response("http://127.0.0.1", "");
+        connector.add_response("https://127.0.0.1", "");
+        connector.add_response("http://fast.domain", "");
+        connector
+    }
+
+    fn is_timed_out(err: Error) -> bool {
+        match err {
+            Error::Io(ref e) => e.kind() == ErrorKind::TimedOut,
+            _ => false
+        }
+    }
+
+    #[test]
+    fn test_max_in_flight() {
+        let limiter = RateLimiter::new(connector(), Limits {
+            max_in_flight: Some(1),
+            max_wait: Some(Duration::from_millis(10)),
+            ..Limits::default()
+        });
+        let first = limiter.connect("127.0.0.1", 80, "http").unwrap();
+        assert!(is_timed_out(limiter.connect("127.0.0.1", 80, "http").err().unwrap()));
+        // other hosts have their own limits
+        let _other = limiter.connect("127.0.0.1", 443, "https").unwrap();
+        drop(first);
+        limiter.connect("127.0.0.1", 80, "http").unwrap();
+    }
+
+    #[test]
+    fn test_requests_per_second() {
+        let mut limiter = RateLimiter::new(connector(), Limits {
+            requests_per_second: Some(1.0),
+            burst: 2,
+            max_wait: Some(Duration::from_millis(0)),
+            ..Limits::default()
+        });
+        limiter.set_host_limits("fast.domain", 80, "http", Limits {
+            requests_per_second: Some(1000.0),
+            max_wait: Some(Duration::from_secs(1)),
+            ..Limits::default()
+        });
+        limiter.connect("127.0.0.1", 80, "http").unwrap();
+        limiter.connect("127.0.0.1", 80, "http").unwrap();
+        assert!(is_timed_out(limiter.connect("127.0.0.1", 80, "http").err().unwrap()));
+
+        for _ in 0..5 {
+            limiter.connect("fast.domain", 80, "http").unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_requests_per_second() {
+        RateLimiter::new(connector(), Limits {
+            requests_per_second: Some(0.0),
+            ..Limits::default()
+        });
+    }
+}
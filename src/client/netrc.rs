@@ -0,0 +1,157 @@
+//! Client Credentials from `.netrc`
+//!
+//! A `Netrc` set on a `Client` supplies `Authorization: Basic` credentials
+//! for requests to the hosts it lists, unless the request already has an
+//! `Authorization` header or credentials in its URL.
+//!
+//! ```no_run
+//! use hyper::Client;
+//! use hyper::client::netrc::Netrc;
+//!
+//! let mut client = Client::new();
+//! client.set_netrc(Some(Netrc::from_home().unwrap()));
+//! ```
+//!
+//! The `machine`, `default`, `login` and `password` entries are used.
+//! `account` entries and `macdef` macros are skipped. The `default` entry is
+//! only used for the host of a request, not for hosts it is redirected to.
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use header::Basic;
+
+/// The credentials listed in a `.netrc` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Netrc {
+    machines: Vec<(String, Basic)>,
+    default: Option<Basic>,
+}
+
+impl Netrc {
+    /// Parse the contents of a `.netrc` file.
+    ///
+    /// Unknown entries are skipped, as are machines without a `login`.
+    pub fn parse(text: &str) -> Netrc {
+        let mut netrc = Netrc::default();
+        // (machine, or None for default, login, password)
+        let mut entry: Option<(Option<String>, Option<String>, Option<String>)> = None;
+        let mut in_macdef = false;
+        for line in text.lines() {
+            if in_macdef {
+                // a macro runs until an empty line
+                in_macdef = !line.trim().is_empty();
+                continue;
+            }
+            let mut tokens = line.split_whitespace();
+            while let Some(token) = tokens.next() {
+                match token {
+                    "machine" | "default" => {
+                        netrc.push(entry.take());
+                        let machine = if token == "machine" {
+                            tokens.next().map(|machine| machine.to_owned())
+                        } else {
+                            None
+                        };
+                        entry = Some((machine, None, None));
+                    },
+                    "login" | "password" | "account" => {
+                        let value = tokens.next().map(|value| value.to_owned());
+                        if let Some(ref mut entry) = entry {
+                            match token {
+                                "login" => entry.1 = value,
+                                "password" => entry.2 = value,
+                                _ => ()
+                            }
+                        }
+                    },
+                    "macdef" => {
+                        in_macdef = true;
+                        break;
+                    },
+                    _ => debug!("unknown netrc token {:?}", token)
+                }
+            }
+        }
+        netrc.push(entry);
+        netrc
+    }
+
+    fn push(&mut self, entry: Option<(Option<String>, Option<String>, Option<String>)>) {
+        if let Some((machine, Some(login), password)) = entry {
+            let basic = Basic {
+                username: login,
+                password: password,
+            };
+            match machine {
+                Some(machine) => self.machines.push((machine, basic)),
+                None => self.default = Some(basic),
+            }
+        }
+    }
+
+    /// Read and parse the `.netrc` file at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ::Result<Netrc> {
+        let mut text = String::new();
+        try!(try!(File::open(path)).read_to_string(&mut text));
+        Ok(Netrc::parse(&text))
+    }
+
+    /// Read and parse the file named by the `NETRC` environment variable, or
+    /// else `.netrc` in the home directory.
+    pub fn from_home() -> ::Result<Netrc> {
+        let path = match env::var_os("NETRC") {
+            Some(path) => PathBuf::from(path),
+            None => env::home_dir().unwrap_or(PathBuf::new()).join(".netrc")
+        };
+        Netrc::from_file(path)
+    }
+
+    /// Get the credentials for `host`, or the default ones.
+    pub fn credentials(&self, host: &str) -> Option<&Basic> {
+        self.machine_credentials(host).or(self.default.as_ref())
+    }
+
+    /// Get the credentials listed for `host`, without the default ones.
+    pub fn machine_credentials(&self, host: &str) -> Option<&Basic> {
+        self.machines.iter()
+            .find(|&&(ref machine, _)| machine == host)
+            .map(|&(_, ref basic)| basic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Basic;
+    use super::Netrc;
+
+    #[test]
+    fn test_parse() {
+        let netrc = Netrc::parse("machine example.domain login alice password s3cret\n\
+                                  machine other.domain\n\
+                                  \tlogin bob\n\
+                                  \taccount acct\n\
+                                  macdef init\n\
+                                  machine macro.domain login nobody\n\
+                                  \n\
+                                  machine nologin.domain password x\n\
+                                  default login anonymous password guest\n");
+        assert_eq!(netrc.credentials("example.domain"), Some(&Basic {
+            username: "alice".to_owned(),
+            password: Some("s3cret".to_owned()),
+        }));
+        assert_eq!(netrc.credentials("other.domain"), Some(&Basic {
+            username: "bob".to_owned(),
+            password: None,
+        }));
+        let anonymous = Some(&Basic {
+            username: "anonymous".to_owned(),
+            password: Some("guest".to_owned()),
+        });
+        assert_eq!(netrc.credentials("macro.domain"), anonymous);
+        assert_eq!(netrc.credentials("nologin.domain"), anonymous);
+        assert_eq!(netrc.machine_credentials("nologin.domain"), None);
+        assert_eq!(Netrc::parse("").credentials("example.domain"), None);
+    }
+}
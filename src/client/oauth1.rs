@@ -0,0 +1,247 @@
+//! OAuth 1.0a Request Signing
+//!
+//! An `OAuth1Signer` signs requests with the credentials of a consumer, and
+//! optionally a token, as described in
+//! [RFC5849](https://tools.ietf.org/html/rfc5849#section-3.4).
+//!
+//! ```no_run
+//! use hyper::Client;
+//! use hyper::client::oauth1::{OAuth1Signer, SignatureMethod};
+//! use hyper::header::{Authorization, ContentType};
+//! use hyper::method::Method;
+//! use hyper::Url;
+//!
+//! let mut signer = OAuth1Signer::new("consumer key", "consumer secret",
+//!                                    SignatureMethod::Plaintext);
+//! signer.set_token(Some(("token".to_owned(), "token secret".to_owned())));
+//!
+//! let url = Url::parse("https://api.example.domain/1/statuses/update").unwrap();
+//! let form = vec![("status".to_owned(), "Hello".to_owned())];
+//! let oauth = signer.sign(&Method::Post, &url, &form);
+//! Client::new().post(url)
+//!     .header(Authorization(oauth))
+//!     .header(ContentType::form_url_encoded())
+//!     .body("status=Hello")
+//!     .send()
+//!     .unwrap();
+//! ```
+//!
+//! The form parameters are those of an `application/x-www-form-urlencoded`
+//! body, which are signed along with the query. Other bodies aren't signed.
+//!
+//! `HMAC-SHA1` signing needs the `openssl` feature.
+#[cfg(not(feature = "openssl"))]
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+#[cfg(feature = "openssl")]
+use openssl::crypto::hash::Type;
+#[cfg(feature = "openssl")]
+use openssl::crypto::hmac::hmac;
+#[cfg(feature = "openssl")]
+use openssl::crypto::rand::rand_bytes;
+#[cfg(feature = "openssl")]
+use serialize::base64::{ToBase64, STANDARD};
+#[cfg(feature = "openssl")]
+use serialize::hex::ToHex;
+use time;
+use url::Url;
+
+use header::OAuth1;
+use method::Method;
+use uri::percent_encode;
+
+/// How an `OAuth1Signer` signs requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureMethod {
+    /// `HMAC-SHA1`, an HMAC of the signature base string.
+    #[cfg(feature = "openssl")]
+    HmacSha1,
+    /// `PLAINTEXT`, the secrets themselves, only fit for TLS connections.
+    Plaintext,
+}
+
+impl SignatureMethod {
+    fn name(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "openssl")]
+            SignatureMethod::HmacSha1 => "HMAC-SHA1",
+            SignatureMethod::Plaintext => "PLAINTEXT",
+        }
+    }
+}
+
+/// Signs requests with OAuth 1.0a.
+#[derive(Clone, Debug)]
+pub struct OAuth1Signer {
+    consumer_key: String,
+    consumer_secret: String,
+    token: Option<(String, String)>,
+    method: SignatureMethod,
+    realm: Option<String>,
+}
+
+impl OAuth1Signer {
+    /// Create a signer with the key and secret of a consumer.
+    pub fn new<S: Into<String>>(consumer_key: S, consumer_secret: S,
+                                method: SignatureMethod) -> OAuth1Signer {
+        OAuth1Signer {
+            consumer_key: consumer_key.into(),
+            consumer_secret: consumer_secret.into(),
+            token: None,
+            method: method,
+            realm: None,
+        }
+    }
+
+    /// Set the token and token secret to sign with.
+    pub fn set_token(&mut self, token: Option<(String, String)>) {
+        self.token = token;
+    }
+
+    /// Set the realm sent in the header, which isn't signed.
+    pub fn set_realm(&mut self, realm: Option<String>) {
+        self.realm = realm;
+    }
+
+    /// Sign a request, with a fresh nonce and the current time.
+    ///
+    /// `form` are the decoded parameters of a form body, if any.
+    pub fn sign(&self, method: &Method, url: &Url, form: &[(String, String)]) -> OAuth1 {
+        self.sign_with(method, url, form, &nonce(), time::get_time().sec)
+    }
+
+    /// Sign a request with a given nonce and timestamp.
+    pub fn sign_with(&self, method: &Method, url: &Url, form: &[(String, String)],
+                     nonce: &str, timestamp: i64) -> OAuth1 {
+        let mut params = vec![
+            ("oauth_consumer_key".to_owned(), self.consumer_key.clone()),
+            ("oauth_nonce".to_owned(), nonce.to_owned()),
+            ("oauth_signature_method".to_owned(), self.method.name().to_owned()),
+            ("oauth_timestamp".to_owned(), timestamp.to_string()),
+        ];
+        if let Some((ref token, _)) = self.token {
+            params.push(("oauth_token".to_owned(), token.clone()));
+        }
+        params.push(("oauth_version".to_owned(), "1.0".to_owned()));
+
+        let secret = self.token.as_ref().map_or("", |&(_, ref secret)| &secret[..]);
+        let key = format!("{}&{}", percent_encode(self.consumer_secret.as_bytes()),
+                          percent_encode(secret.as_bytes()));
+        let signature = match self.method {
+            #[cfg(feature = "openssl")]
+            SignatureMethod::HmacSha1 => {
+                let base = base_string(method, url, form, &params);
+                trace!("oauth1 signature base string: {:?}", base);
+                hmac(Type::SHA1, key.as_bytes(), base.as_bytes()).to_base64(STANDARD)
+            },
+            SignatureMethod::Plaintext => key,
+        };
+        params.push(("oauth_signature".to_owned(), signature));
+        params.sort();
+
+        OAuth1 {
+            realm: self.realm.clone(),
+            params: params,
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+fn nonce() -> String {
+    rand_bytes(16).to_hex()
+}
+
+// Without a random source, the nonce is only unique, which is all that
+// RFC5849 asks of it.
+#[cfg(not(feature = "openssl"))]
+fn nonce() -> String {
+    static COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+    format!("{:x}-{:x}", time::precise_time_ns(), COUNTER.fetch_add(1, Ordering::SeqCst))
+}
+
+#[cfg(feature = "openssl")]
+fn base_string(method: &Method, url: &Url, form: &[(String, String)],
+               oauth: &[(String, String)]) -> String {
+    let mut params: Vec<(String, String)> = url.query_pairs().unwrap_or(Vec::new())
+        .iter()
+        .chain(form.iter())
+        .chain(oauth.iter())
+        .map(|&(ref name, ref value)| {
+            (percent_encode(name.as_bytes()), percent_encode(value.as_bytes()))
+        })
+        .collect();
+    params.sort();
+    let params = params.iter()
+        .map(|&(ref name, ref value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}&{}&{}", method, percent_encode(base_uri(url).as_bytes()),
+            percent_encode(params.as_bytes()))
+}
+
+// The URL without its query and fragment, with the port left out if it is
+// the default one.
+#[cfg(feature = "openssl")]
+fn base_uri(url: &Url) -> String {
+    let host = url.serialize_host().unwrap_or(String::new());
+    let path = url.serialize_path().unwrap_or("/".to_owned());
+    match (url.port(), url.port_or_default()) {
+        (Some(port), Some(default)) if port != default => {
+            format!("{}://{}:{}{}", url.scheme, host, port, path)
+        },
+        _ => format!("{}://{}{}", url.scheme, host, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use method::Method;
+    use super::{OAuth1Signer, SignatureMethod};
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn test_hmac_sha1() {
+        use header::{Authorization, Headers};
+
+        // the example of the Twitter API documentation
+        let mut signer = OAuth1Signer::new("xvz1evFS4wEEPTGEFPHBog",
+                                           "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+                                           SignatureMethod::HmacSha1);
+        signer.set_token(Some(("370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb".to_owned(),
+                               "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE".to_owned())));
+        let url = Url::parse("https://api.twitter.com/1.1/statuses/update.json\
+                              ?include_entities=true").unwrap();
+        let form = vec![("status".to_owned(),
+                         "Hello Ladies + Gentlemen, a signed OAuth request!".to_owned())];
+        let oauth = signer.sign_with(&Method::Post, &url, &form,
+                                     "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg", 1318622958);
+        assert_eq!(oauth.get("oauth_signature"), Some("hCtSmYh+iHYCEqBWrE7C7hYmtUk="));
+
+        let mut headers = Headers::new();
+        headers.set(Authorization(oauth));
+        assert_eq!(headers.to_string(),
+                   "Authorization: OAuth \
+                    oauth_consumer_key=\"xvz1evFS4wEEPTGEFPHBog\", \
+                    oauth_nonce=\"kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg\", \
+                    oauth_signature=\"hCtSmYh%2BiHYCEqBWrE7C7hYmtUk%3D\", \
+                    oauth_signature_method=\"HMAC-SHA1\", \
+                    oauth_timestamp=\"1318622958\", \
+                    oauth_token=\"370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb\", \
+                    oauth_version=\"1.0\"\r\n");
+    }
+
+    #[test]
+    fn test_plaintext() {
+        let mut signer = OAuth1Signer::new("dpf43f3p2l4k3l03", "kd94hf93k423kf44",
+                                           SignatureMethod::Plaintext);
+        signer.set_realm(Some("Photos".to_owned()));
+        let url = Url::parse("https://photos.example.net/initiate").unwrap();
+        let oauth = signer.sign(&Method::Post, &url, &[]);
+        assert_eq!(oauth.realm, Some("Photos".to_owned()));
+        assert_eq!(oauth.get("oauth_signature"), Some("kd94hf93k423kf44&"));
+        assert_eq!(oauth.get("oauth_signature_method"), Some("PLAINTEXT"));
+        assert_eq!(oauth.get("oauth_token"), None);
+    }
+}
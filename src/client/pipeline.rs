@@ -0,0 +1,484 @@
+//! HTTP/1.1 Request Pipelining
+//!
+//! A `Pipeline` writes several requests to one connection before reading
+//! their responses, which are returned in the order the requests were
+//! queued.
+//!
+//! Only idempotent methods can be pipelined, since if the server closes the
+//! connection part-way through, the requests still waiting for a response
+//! are sent again on a new connection.
+//!
+//! ```no_run
+//! use hyper::client::Pipeline;
+//!
+//! let mut pipeline = Pipeline::new("http://example.domain").unwrap();
+//! pipeline.get("http://example.domain/a").unwrap();
+//! pipeline.get("http://example.domain/b").unwrap();
+//!
+//! for res in pipeline {
+//!     let res = res.unwrap();
+//!     println!("{}: {}", res.url, res.status);
+//! }
+//! ```
+use std::collections::VecDeque;
+use std::io::{self, BufWriter, Read, Write};
+use std::net::Shutdown;
+
+use url::Url;
+
+use buffer::BufReader;
+use client::{IntoUrl, get_host_and_port};
+use header::{Headers, ContentLength, Host};
+use http::{self, RawStatus, RequestHead};
+use http::h1::{self, TrailersReader};
+use http::h1::HttpReader::EofReader;
+use method::Method;
+use net::{NetworkConnector, NetworkStream, DefaultConnector};
+use status::StatusCode;
+use version::HttpVersion;
+use Error;
+
+// The longest response body a `Pipeline` reads by default.
+const DEFAULT_MAX_BODY_SIZE: u64 = 8 * 1024 * 1024;
+
+/// A request queued on a `Pipeline`.
+#[derive(Clone, Debug)]
+pub struct PipelinedRequest {
+    /// The method of the request. It must be idempotent.
+    pub method: Method,
+    /// The target URL of the request.
+    pub url: Url,
+    /// Additional headers to send with the request.
+    pub headers: Headers,
+    /// An optional body, sent with a `Content-Length`.
+    pub body: Option<Vec<u8>>,
+}
+
+impl PipelinedRequest {
+    /// Create a request with no additional headers, and no body.
+    pub fn new(method: Method, url: Url) -> PipelinedRequest {
+        PipelinedRequest {
+            method: method,
+            url: url,
+            headers: Headers::new(),
+            body: None,
+        }
+    }
+}
+
+/// A response read from a `Pipeline`, with its whole body.
+#[derive(Debug)]
+pub struct PipelinedResponse {
+    /// The status from the server.
+    pub status: StatusCode,
+    /// The raw status code and reason.
+    pub status_raw: RawStatus,
+    /// The headers from the server.
+    pub headers: Headers,
+    /// The HTTP version of this response from the server.
+    pub version: HttpVersion,
+    /// The URL of the request this response answers.
+    pub url: Url,
+    /// The body of the response, no longer than the `Pipeline`'s maximum
+    /// body size.
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Queued {
+    req: PipelinedRequest,
+    attempts: usize,
+}
+
+/// A connection that pipelines requests to one host.
+///
+/// Up to `depth` requests are written before waiting for the first response.
+/// If the connection fails, or the server closes it, any request without a
+/// response is sent again on a new connection, up to `max_retries` times.
+///
+/// Each response body is read whole, since the next response follows it on
+/// the connection. A body longer than `max_body_size` fails its request
+/// with `Error::TooLarge`.
+pub struct Pipeline<C: NetworkConnector = DefaultConnector> {
+    connector: C,
+    host: String,
+    port: u16,
+    scheme: String,
+    depth: usize,
+    max_retries: usize,
+    max_body_size: u64,
+    // requests not yet written
+    pending: VecDeque<Queued>,
+    // requests written on `conn`, waiting for a response
+    in_flight: VecDeque<Queued>,
+    conn: Option<BufReader<Box<NetworkStream + Send>>>,
+}
+
+impl Pipeline<DefaultConnector> {
+    /// Create a `Pipeline` to the host of `url`, using a `DefaultConnector`.
+    pub fn new<U: IntoUrl>(url: U) -> ::Result<Pipeline<DefaultConnector>> {
+        Pipeline::with_connector(url, DefaultConnector::default())
+    }
+}
+
+impl<C: NetworkConnector> Pipeline<C> {
+    /// Create a `Pipeline` to the host of `url`, using the given connector.
+    pub fn with_connector<U: IntoUrl>(url: U, connector: C) -> ::Result<Pipeline<C>> {
+        let url = try!(url.into_url());
+        let (host, port) = try!(get_host_and_port(&url));
+        Ok(Pipeline {
+            connector: connector,
+            host: host,
+            port: port,
+            scheme: url.scheme.clone(),
+            depth: 8,
+            max_retries: 2,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            pending: VecDeque::new(),
+            in_flight: VecDeque::new(),
+            conn: None,
+        })
+    }
+
+    /// Set the maximum number of requests written before their responses
+    /// are read.
+    ///
+    /// The default is 8. A depth of 1 disables pipelining.
+    pub fn set_depth(&mut self, depth: usize) {
+        assert!(depth != 0, "Can't pipeline with a depth of 0.");
+        self.depth = depth;
+    }
+
+    /// Set how many times a request is sent again after its connection failed.
+    ///
+    /// The default is 2.
+    pub fn set_max_retries(&mut self, retries: usize) {
+        self.max_retries = retries;
+    }
+
+    /// Set the longest response body to read, in bytes.
+    ///
+    /// The default is 8MB.
+    pub fn set_max_body_size(&mut self, max: u64) {
+        self.max_body_size = max;
+    }
+
+    /// Queue a GET request.
+    pub fn get<U: IntoUrl>(&mut self, url: U) -> ::Result<()> {
+        let url = try!(url.into_url());
+        self.push(PipelinedRequest::new(Method::Get, url))
+    }
+
+    /// Queue a request.
+    ///
+    /// Returns an `InvalidInput` IO error if the method is not idempotent, or
+    /// the URL is not on this `Pipeline`'s host.
+    pub fn push(&mut self, req: PipelinedRequest) -> ::Result<()> {
+        if !req.method.idempotent() {
+            debug!("cannot pipeline {:?}", req.method);
+            return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput,
+                                                "only idempotent requests can be pipelined")));
+        }
+        let (host, port) = try!(get_host_and_port(&req.url));
+        if host != self.host || port != self.port || req.url.scheme != self.scheme {
+            debug!("cannot pipeline {} on {}://{}:{}", req.url, self.scheme, self.host, self.port);
+            return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput,
+                                                "request is not for the pipelined host")));
+        }
+        self.pending.push_back(Queued { req: req, attempts: 0 });
+        Ok(())
+    }
+
+    /// The number of queued requests without a response yet.
+    pub fn len(&self) -> usize {
+        self.pending.len() + self.in_flight.len()
+    }
+
+    /// Read the response to the oldest queued request.
+    ///
+    /// Returns `None` once every queued request has been answered.
+    pub fn next_response(&mut self) -> Option<::Result<PipelinedResponse>> {
+        loop {
+            if self.in_flight.is_empty() && self.pending.is_empty() {
+                return None;
+            }
+
+            if let Err(e) = self.fill() {
+                self.fail_over();
+                if let Some(err) = self.give_up(e) {
+                    return Some(Err(err));
+                }
+                continue;
+            }
+
+            match self.read_response() {
+                Ok(res) => return Some(Ok(res)),
+                Err(Error::Io(e)) => {
+                    debug!("pipeline connection failed: {:?}", e);
+                    self.fail_over();
+                    if let Some(err) = self.give_up(Error::Io(e)) {
+                        return Some(Err(err));
+                    }
+                },
+                Err(e) => {
+                    // the stream can't be trusted to be at the start of the
+                    // next response anymore
+                    self.in_flight.pop_front();
+                    self.fail_over();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    /// Connect if needed, and write pending requests up to `depth`.
+    fn fill(&mut self) -> ::Result<()> {
+        if self.conn.is_none() {
+            match self.connector.connect(&self.host, self.port, &self.scheme) {
+                Ok(stream) => self.conn = Some(BufReader::new(stream.into())),
+                Err(e) => {
+                    // a failed connect counts as an attempt of the oldest
+                    // request, so an unreachable host isn't retried forever
+                    if let Some(queued) = self.pending.front_mut() {
+                        queued.attempts += 1;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        let conn = self.conn.as_mut().unwrap();
+        while self.in_flight.len() < self.depth {
+            let mut queued = match self.pending.pop_front() {
+                Some(queued) => queued,
+                None => break
+            };
+            queued.attempts += 1;
+            let result = write_request(conn.get_mut(), &queued.req);
+            self.in_flight.push_back(queued);
+            try!(result);
+        }
+        try!(conn.get_mut().flush());
+        Ok(())
+    }
+
+    fn read_response(&mut self) -> ::Result<PipelinedResponse> {
+        let (res, reusable) = {
+            let conn = self.conn.as_mut().unwrap();
+            let req = &self.in_flight.front().unwrap().req;
+
+            let mut head = try!(h1::parse_response(conn));
+            while head.subject.0 >= 100 && head.subject.0 < 200 {
+                trace!("skipping interim response {:?}", head.subject);
+                head = try!(h1::parse_response(conn));
+            }
+
+            let mut body = Vec::new();
+            let reusable = {
+                let mut reader = match h1::response_reader(&req.method, &head, &mut *conn) {
+                    Ok(reader) => TrailersReader::new(reader),
+                    Err((e, _)) => return Err(e)
+                };
+                let limit = self.max_body_size.saturating_add(1);
+                try!(reader.by_ref().take(limit).read_to_end(&mut body));
+                if body.len() as u64 > self.max_body_size {
+                    debug!("pipelined body is longer than {} bytes", self.max_body_size);
+                    return Err(Error::TooLarge);
+                }
+                match *reader.get_ref() {
+                    EofReader(..) => false,
+                    _ => http::should_keep_alive(head.version, &head.headers)
+                }
+            };
+
+            (PipelinedResponse {
+                status: StatusCode::from_u16(head.subject.0),
+                status_raw: head.subject,
+                headers: head.headers,
+                version: head.version,
+                url: req.url.clone(),
+                body: body,
+            }, reusable)
+        };
+
+        self.in_flight.pop_front();
+        if !reusable {
+            debug!("pipeline connection will not be reused");
+            self.fail_over();
+        }
+        Ok(res)
+    }
+
+    /// Drop the connection, moving every request still waiting for a
+    /// response back in front of the pending requests, in order.
+    fn fail_over(&mut self) {
+        if let Some(mut conn) = self.conn.take() {
+            let _ = conn.get_mut().close(Shutdown::Both);
+        }
+        while let Some(queued) = self.in_flight.pop_back() {
+            self.pending.push_front(queued);
+        }
+    }
+
+    /// Returns the error if the oldest request has run out of retries,
+    /// removing it from the queue.
+    fn give_up(&mut self, err: Error) -> Option<Error> {
+        let exhausted = match self.pending.front() {
+            Some(queued) => queued.attempts > self.max_retries,
+            None => false
+        };
+        if exhausted {
+            self.pending.pop_front();
+            Some(err)
+        } else {
+            None
+        }
+    }
+}
+
+impl<C: NetworkConnector> Iterator for Pipeline<C> {
+    type Item = ::Result<PipelinedResponse>;
+
+    fn next(&mut self) -> Option<::Result<PipelinedResponse>> {
+        self.next_response()
+    }
+}
+
+fn write_request<W: Write>(stream: W, req: &PipelinedRequest) -> ::Result<()> {
+    // the same headers as a `Request`, which the request's own may replace
+    let (host, port) = try!(get_host_and_port(&req.url));
+    let mut headers = Headers::new();
+    headers.set(Host {
+        hostname: host,
+        port: Some(port),
+    });
+    headers.extend(req.headers.iter());
+    match req.body {
+        Some(ref body) => headers.set(ContentLength(body.len() as u64)),
+        None => match req.method {
+            Method::Get | Method::Head => (),
+            _ => headers.set(ContentLength(0))
+        }
+    }
+
+    let head = RequestHead {
+        headers: headers,
+        method: req.method.clone(),
+        url: req.url.clone(),
+    };
+    let mut writer = match h1::start_request(BufWriter::new(stream), head, false) {
+        Ok((writer, _)) => writer,
+        Err((e, _)) => return Err(Error::Io(e))
+    };
+    if let Some(ref body) = req.body {
+        try!(writer.write_all(body));
+    }
+    try!(writer.end().map_err(io::Error::from));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::str::from_utf8;
+
+    use testing::{MockConnector, MockStream};
+    use header::Host;
+    use method::Method;
+    use net::NetworkConnector;
+    use status::StatusCode;
+    use url::Url;
+    use Error;
+
+    use super::{Pipeline, PipelinedRequest};
+
+    mock_connector!(TwoResponses {
+        "http://127.0.0.1" => "HTTP/1.1 200 OK\r\nContent-Length: 1\r\n\r\na\
+                               HTTP/1.1 404 Not Found\r\nContent-Length: 1\r\n\r\nb"
+    });
+
+    #[test]
+    fn test_pipeline_in_order() {
+        let mut pipeline = Pipeline::with_connector("http://127.0.0.1", TwoResponses).unwrap();
+        pipeline.get("http://127.0.0.1/a").unwrap();
+        pipeline.get("http://127.0.0.1/b").unwrap();
+
+        let a = pipeline.next_response().unwrap().unwrap();
+        assert_eq!(a.status, StatusCode::Ok);
+        assert_eq!(a.body, b"a");
+        assert_eq!(a.url.serialize(), "http://127.0.0.1/a");
+
+        let b = pipeline.next_response().unwrap().unwrap();
+        assert_eq!(b.status, StatusCode::NotFound);
+        assert_eq!(b.body, b"b");
+
+        assert!(pipeline.next_response().is_none());
+    }
+
+    #[test]
+    fn test_pipeline_rejects_non_idempotent() {
+        let mut pipeline = Pipeline::with_connector("http://127.0.0.1", TwoResponses).unwrap();
+        let url = Url::parse("http://127.0.0.1/a").unwrap();
+        assert!(pipeline.push(PipelinedRequest::new(Method::Post, url)).is_err());
+        assert!(pipeline.get("http://127.0.0.2/a").is_err());
+        assert_eq!(pipeline.len(), 0);
+    }
+
+    #[test]
+    fn test_pipeline_request_head() {
+        let mut connector = MockConnector::new();
+        connector.add_response("http://127.0.0.1", "HTTP/1.1 204 No Content\r\n\r\n");
+        let mut pipeline = Pipeline::with_connector("http://127.0.0.1", connector.clone())
+            .unwrap();
+        let mut req = PipelinedRequest::new(Method::Put,
+                                            Url::parse("http://127.0.0.1/a?b").unwrap());
+        req.headers.set(Host { hostname: "example.domain".to_owned(), port: None });
+        req.body = Some(b"hi".to_vec());
+        pipeline.push(req).unwrap();
+        assert_eq!(pipeline.next_response().unwrap().unwrap().status, StatusCode::NoContent);
+
+        let written = String::from_utf8(connector.streams()[0].written()).unwrap();
+        assert!(written.starts_with("PUT /a?b HTTP/1.1\r\n"));
+        assert!(written.contains("Host: example.domain\r\n"));
+        assert!(written.contains("Content-Length: 2\r\n"));
+        assert!(written.ends_with("\r\n\r\nhi"));
+    }
+
+    #[test]
+    fn test_pipeline_max_body_size() {
+        let mut pipeline = Pipeline::with_connector("http://127.0.0.1", TwoResponses).unwrap();
+        pipeline.set_max_body_size(0);
+        pipeline.get("http://127.0.0.1/a").unwrap();
+        match pipeline.next_response() {
+            Some(Err(Error::TooLarge)) => (),
+            other => panic!("expected TooLarge, got {:?}", other)
+        }
+        assert!(pipeline.next_response().is_none());
+    }
+
+    // The first connection answers one request, then closes.
+    struct ClosingConnector(Cell<usize>);
+
+    impl NetworkConnector for ClosingConnector {
+        type Stream = MockStream;
+
+        fn connect(&self, _: &str, _: u16, _: &str) -> ::Result<MockStream> {
+            let n = self.0.get();
+            self.0.set(n + 1);
+            let body = if n == 0 { "a" } else { "b" };
+            let res = format!("HTTP/1.1 200 OK\r\nContent-Length: 1\r\n\r\n{}", body);
+            Ok(MockStream::with_input(res.as_bytes()))
+        }
+    }
+
+    #[test]
+    fn test_pipeline_fail_over() {
+        let mut pipeline = Pipeline::with_connector("http://127.0.0.1",
+                                                    ClosingConnector(Cell::new(0))).unwrap();
+        pipeline.get("http://127.0.0.1/a").unwrap();
+        pipeline.get("http://127.0.0.1/b").unwrap();
+
+        let bodies = pipeline.map(|res| res.unwrap().body).collect::<Vec<_>>();
+        let bodies = bodies.iter().map(|b| from_utf8(b).unwrap()).collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["a", "b"]);
+    }
+}
@@ -8,7 +8,11 @@ use std::sync::{Arc, Mutex};
 #[cfg(feature = "timeouts")]
 use std::time::Duration;
 
-use net::{NetworkConnector, NetworkStream, DefaultConnector};
+use time::precise_time_ns;
+
+use net::{NetworkConnector, NetworkStream, StreamCloser, TlsInfo, ConnectTiming,
+          DefaultConnector, ns_to_duration};
+use super::{Key, key};
 
 /// The `NetworkConnector` that behaves as a connection pool used by hyper's `Client`.
 pub struct Pool<C: NetworkConnector> {
@@ -38,29 +42,6 @@ struct PoolImpl<S> {
     config: Config,
 }
 
-type Key = (String, u16, Scheme);
-
-fn key<T: Into<Scheme>>(host: &str, port: u16, scheme: T) -> Key {
-    (host.to_owned(), port, scheme.into())
-}
-
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
-enum Scheme {
-    Http,
-    Https,
-    Other(String)
-}
-
-impl<'a> From<&'a str> for Scheme {
-    fn from(s: &'a str) -> Scheme {
-        match s {
-            "http" => Scheme::Http,
-            "https" => Scheme::Https,
-            s => Scheme::Other(String::from(s))
-        }
-    }
-}
-
 impl Pool<DefaultConnector> {
     /// Creates a `Pool` with a `DefaultConnector`.
     #[inline]
@@ -102,29 +83,48 @@ impl<S> PoolImpl<S> {
 impl<C: NetworkConnector<Stream=S>, S: NetworkStream + Send> NetworkConnector for Pool<C> {
     type Stream = PooledStream<S>;
     fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<PooledStream<S>> {
+        self.connect_timed(host, port, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_timed(&self, host: &str, port: u16, scheme: &str)
+                     -> ::Result<(PooledStream<S>, ConnectTiming)> {
+        let start = precise_time_ns();
         let key = key(host, port, scheme);
         let mut locked = self.inner.lock().unwrap();
         let mut should_remove = false;
-        let inner = match locked.conns.get_mut(&key) {
+        let mut reused = false;
+        let idle = match locked.conns.get_mut(&key) {
             Some(ref mut vec) => {
                 trace!("Pool had connection, using");
                 should_remove = vec.len() == 1;
-                vec.pop().unwrap()
+                reused = true;
+                vec.pop()
             }
-            _ => PooledStreamInner {
-                key: key.clone(),
-                stream: try!(self.connector.connect(host, port, scheme)),
-                previous_response_expected_no_content: false,
+            _ => None
+        };
+        let pool_ns = precise_time_ns() - start;
+        let (inner, mut timing) = match idle {
+            Some(inner) => (inner, ConnectTiming::default()),
+            None => {
+                let (stream, timing) = try!(self.connector.connect_timed(host, port, scheme));
+                (PooledStreamInner {
+                    key: key.clone(),
+                    stream: stream,
+                    previous_response_expected_no_content: false,
+                }, timing)
             }
         };
         if should_remove {
             locked.conns.remove(&key);
         }
-        Ok(PooledStream {
+        timing.pool = Some(ns_to_duration(pool_ns));
+        Ok((PooledStream {
             inner: Some(inner),
             is_closed: false,
+            reused: reused,
+            timing: timing,
             pool: self.inner.clone(),
-        })
+        }, timing))
     }
 }
 
@@ -132,6 +132,8 @@ impl<C: NetworkConnector<Stream=S>, S: NetworkStream + Send> NetworkConnector fo
 pub struct PooledStream<S> {
     inner: Option<PooledStreamInner<S>>,
     is_closed: bool,
+    reused: bool,
+    timing: ConnectTiming,
     pool: Arc<Mutex<PoolImpl<S>>>,
 }
 
@@ -194,6 +196,26 @@ impl<S: NetworkStream> NetworkStream for PooledStream<S> {
         self.inner.as_mut().unwrap().stream.close(how)
     }
 
+    #[inline]
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        self.inner.as_ref().unwrap().stream.closer()
+    }
+
+    #[inline]
+    fn is_reused(&self) -> bool {
+        self.reused
+    }
+
+    #[inline]
+    fn tls_info(&self) -> Option<TlsInfo> {
+        self.inner.as_ref().unwrap().stream.tls_info()
+    }
+
+    #[inline]
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        Some(self.timing)
+    }
+
     #[inline]
     fn set_previous_response_expected_no_content(&mut self, expected: bool) {
         trace!("set_previous_response_expected_no_content {}", expected);
@@ -226,14 +248,17 @@ impl<S> Drop for PooledStream<S> {
 mod tests {
     use std::net::Shutdown;
     use std::io::Read;
-    use mock::{MockConnector};
+    use testing::MockConnector;
     use net::{NetworkConnector, NetworkStream};
 
-    use super::{Pool, key};
+    use client::key;
+    use super::Pool;
 
     macro_rules! mocked {
         () => ({
-            Pool::with_connector(Default::default(), MockConnector)
+            let mut connector = MockConnector::new();
+            connector.add_response("http://127.0.0.1", "");
+            Pool::with_connector(Default::default(), connector)
         })
     }
 
@@ -255,6 +280,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_reused() {
+        let pool = mocked!();
+        assert!(!pool.connect("127.0.0.1", 3000, "http").unwrap().is_reused());
+        assert!(pool.connect("127.0.0.1", 3000, "http").unwrap().is_reused());
+        assert!(!pool.connect("127.0.0.1", 3001, "http").unwrap().is_reused());
+    }
+
+    #[test]
+    fn test_connect_timing() {
+        let pool = mocked!();
+        let (stream, timing) = pool.connect_timed("127.0.0.1", 3000, "http").unwrap();
+        assert!(timing.pool.is_some());
+        assert_eq!(stream.connect_timing(), Some(timing));
+        drop(stream);
+
+        let stream = pool.connect("127.0.0.1", 3000, "http").unwrap();
+        let timing = stream.connect_timing().unwrap();
+        assert!(timing.pool.is_some());
+        assert!(timing.dns.is_none());
+        assert!(timing.connect.is_none());
+    }
+
     #[test]
     fn test_closed() {
         let pool = mocked!();
@@ -1,8 +1,6 @@
 //! Client Requests
 use std::marker::PhantomData;
 use std::io::{self, Write};
-
-#[cfg(feature = "timeouts")]
 use std::time::Duration;
 
 use url::Url;
@@ -129,12 +127,58 @@ impl Request<Fresh> {
 }
 
 impl Request<Streaming> {
+    /// Waits up to `timeout` for the server to answer `100 Continue`, if the
+    /// request was started with an `Expect: 100-continue` header.
+    ///
+    /// Returns `false` if the server sent a final response instead, such as
+    /// `401 Unauthorized` or `413 Payload Too Large`. The body should then
+    /// not be written, and `send` will return that response.
+    ///
+    /// If the server doesn't answer within `timeout`, this returns `true`, so
+    /// the body is written anyway.
+    ///
+    /// **NOTE**: Waiting requires the `timeouts` feature. Without it, this
+    /// always returns `true` immediately.
+    pub fn wait_continue(&mut self, timeout: Duration) -> ::Result<bool> {
+        match self.message.wait_continue(timeout) {
+            Ok(send_body) => Ok(send_body),
+            Err(e) => {
+                let _ = self.message.close_connection();
+                Err(e)
+            }
+        }
+    }
+
+    /// Sets trailers to send after the body, such as a checksum of it.
+    ///
+    /// Trailers can only follow a chunked body, so they are dropped if the
+    /// request has a `Content-Length`.
+    #[inline]
+    pub fn set_trailers(&mut self, trailers: Headers) {
+        self.message.set_trailers(trailers);
+    }
+
     /// Completes writing the request, and returns a response to read from.
     ///
     /// Consumes the Request.
     pub fn send(self) -> ::Result<Response> {
         Response::with_message(self.url, self.message)
     }
+
+    /// Writes `data` as exactly one chunk of a chunked body, with the given
+    /// extensions.
+    ///
+    /// See `HttpWriter::write_chunk`.
+    pub fn write_chunk(&mut self, data: &[u8], extensions: &[(&str, Option<&str>)])
+            -> io::Result<()> {
+        match self.message.write_chunk(data, extensions) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let _ = self.message.close_connection();
+                Err(e)
+            }
+        }
+    }
 }
 
 impl Write for Request<Streaming> {
@@ -167,13 +211,20 @@ mod tests {
     use std::str::from_utf8;
     use url::Url;
     use method::Method::{Get, Head, Post};
-    use mock::{MockStream, MockConnector};
+    use testing::{MockStream, MockConnector};
     use net::Fresh;
-    use header::{ContentLength,TransferEncoding,Encoding};
+    use header::{Headers,ContentLength,TransferEncoding,Encoding};
     use url::form_urlencoded;
     use super::Request;
     use http::h1::Http11Message;
 
+    fn connector() -> MockConnector {
+        let mut connector = MockConnector::new();
+        connector.add_response("http://example.dom", "");
+        connector.add_response("http://hyper.rs", "");
+        connector
+    }
+
     fn run_request(req: Request<Fresh>) -> Vec<u8> {
         let req = req.start().unwrap();
         let message = req.message;
@@ -181,7 +232,7 @@ mod tests {
         message.flush_outgoing().unwrap();
         let stream = *message
             .into_inner().downcast::<MockStream>().ok().unwrap();
-        stream.write
+        stream.written()
     }
 
     fn assert_no_body(s: &str) {
@@ -192,7 +243,7 @@ mod tests {
     #[test]
     fn test_get_empty_body() {
         let req = Request::with_connector(
-            Get, Url::parse("http://example.dom").unwrap(), &mut MockConnector
+            Get, Url::parse("http://example.dom").unwrap(), &connector()
         ).unwrap();
         let bytes = run_request(req);
         let s = from_utf8(&bytes[..]).unwrap();
@@ -202,7 +253,7 @@ mod tests {
     #[test]
     fn test_head_empty_body() {
         let req = Request::with_connector(
-            Head, Url::parse("http://example.dom").unwrap(), &mut MockConnector
+            Head, Url::parse("http://example.dom").unwrap(), &connector()
         ).unwrap();
         let bytes = run_request(req);
         let s = from_utf8(&bytes[..]).unwrap();
@@ -213,18 +264,32 @@ mod tests {
     fn test_url_query() {
         let url = Url::parse("http://example.dom?q=value").unwrap();
         let req = Request::with_connector(
-            Get, url, &mut MockConnector
+            Get, url, &connector()
         ).unwrap();
         let bytes = run_request(req);
         let s = from_utf8(&bytes[..]).unwrap();
         assert!(s.contains("?q=value"));
     }
 
+    #[test]
+    fn test_proxied_absolute_uri() {
+        use http::HttpMessage;
+        let url = Url::parse("http://example.dom/path?q=value#frag").unwrap();
+        let mut req = Request::with_connector(
+            Get, url, &connector()
+        ).unwrap();
+        req.message.set_proxied(true);
+        let bytes = run_request(req);
+        let s = from_utf8(&bytes[..]).unwrap();
+        assert!(s.starts_with("GET http://example.dom/path?q=value HTTP/1.1\r\n"));
+        assert!(s.contains("Host: example.dom"));
+    }
+
     #[test]
     fn test_post_content_length() {
         let url = Url::parse("http://example.dom").unwrap();
         let mut req = Request::with_connector(
-            Post, url, &mut MockConnector
+            Post, url, &connector()
         ).unwrap();
         let body = form_urlencoded::serialize(vec!(("q","value")).into_iter());
         req.headers_mut().set(ContentLength(body.len() as u64));
@@ -237,7 +302,7 @@ mod tests {
     fn test_post_chunked() {
         let url = Url::parse("http://example.dom").unwrap();
         let req = Request::with_connector(
-            Post, url, &mut MockConnector
+            Post, url, &connector()
         ).unwrap();
         let bytes = run_request(req);
         let s = from_utf8(&bytes[..]).unwrap();
@@ -248,7 +313,7 @@ mod tests {
     fn test_post_chunked_with_encoding() {
         let url = Url::parse("http://example.dom").unwrap();
         let mut req = Request::with_connector(
-            Post, url, &mut MockConnector
+            Post, url, &connector()
         ).unwrap();
         req.headers_mut().set(TransferEncoding(vec![Encoding::Chunked]));
         let bytes = run_request(req);
@@ -257,23 +322,64 @@ mod tests {
         assert!(s.contains("Transfer-Encoding:"));
     }
 
+    #[test]
+    fn test_post_chunked_trailers() {
+        let url = Url::parse("http://example.dom").unwrap();
+        let req = Request::with_connector(
+            Post, url, &connector()
+        ).unwrap();
+        let mut req = req.start().unwrap();
+        req.write_all(b"foo").unwrap();
+        let mut trailers = Headers::new();
+        trailers.set_raw("Content-MD5", vec![b"abc".to_vec()]);
+        req.set_trailers(trailers);
+
+        let mut message = req.message.downcast::<Http11Message>().ok().unwrap();
+        message.flush_outgoing().unwrap();
+        let stream = *message
+            .into_inner().downcast::<MockStream>().ok().unwrap();
+        let written = stream.written();
+        let s = from_utf8(&written[..]).unwrap();
+        assert!(s.ends_with("3\r\nfoo\r\n0\r\nContent-MD5: abc\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_post_chunk_extensions() {
+        let url = Url::parse("http://example.dom").unwrap();
+        let req = Request::with_connector(
+            Post, url, &connector()
+        ).unwrap();
+        let mut req = req.start().unwrap();
+        req.write_chunk(b"foo", &[("id", Some("1"))]).unwrap();
+        req.write_chunk(b"bar", &[]).unwrap();
+        assert!(req.write_chunk(b"", &[]).is_err());
+
+        let mut message = req.message.downcast::<Http11Message>().ok().unwrap();
+        message.flush_outgoing().unwrap();
+        let stream = *message
+            .into_inner().downcast::<MockStream>().ok().unwrap();
+        let written = stream.written();
+        let s = from_utf8(&written[..]).unwrap();
+        assert!(s.ends_with("3;id=1\r\nfoo\r\n3\r\nbar\r\n0\r\n\r\n"));
+    }
+
     #[test]
     fn test_write_error_closes() {
         let url = Url::parse("http://hyper.rs").unwrap();
         let req = Request::with_connector(
-            Get, url, &mut MockConnector
+            Get, url, &connector()
         ).unwrap();
         let mut req = req.start().unwrap();
 
         req.message.downcast_mut::<Http11Message>().unwrap()
             .get_mut().downcast_mut::<MockStream>().unwrap()
-            .error_on_write = true;
+            .fail_writes(true);
 
         req.write(b"foo").unwrap();
         assert!(req.flush().is_err());
 
         assert!(req.message.downcast_ref::<Http11Message>().unwrap()
             .get_ref().downcast_ref::<MockStream>().unwrap()
-            .is_closed);
+            .is_closed());
     }
 }
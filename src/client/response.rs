@@ -1,12 +1,28 @@
 //! Client Responses
+use std::fmt;
 use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::u64;
 
+use time::precise_time_ns;
 use url::Url;
 
+#[cfg(feature = "json")]
+use serialize::Decodable;
+#[cfg(feature = "json")]
+use serialize::json;
+
 use header;
-use net::NetworkStream;
-use http::{self, RawStatus, ResponseHead, HttpMessage};
+use header::Charset;
+use mime::{Mime, Attr, Value};
+use net::{NetworkStream, TlsInfo, ns_to_duration};
+use http::{self, Chunk, RawStatus, ResponseHead, HttpMessage};
 use http::h1::Http11Message;
+use client::{AbortHandle, ClientResponse, aborted_io_error, capture_body,
+             DEFAULT_DRAIN_LIMIT};
+use client::har::{HarEntry, HarRecorder};
 use status;
 use version;
 
@@ -23,6 +39,63 @@ pub struct Response {
     pub url: Url,
     status_raw: RawStatus,
     message: Box<HttpMessage>,
+    abort: Option<AbortHandle>,
+    drain_limit: u64,
+    timing: Timing,
+    timing_start: Option<u64>,
+    on_timing: Option<TimingCallback>,
+    har: Option<(HarRecorder, HarEntry)>,
+}
+
+/// How long each phase of a request took, like the timing of `curl -w`.
+///
+/// Phases are `None` if they didn't happen, or weren't measured. Only a
+/// `Pool` measures the phases of opening a connection, so they are all
+/// `None` for a `Client` with another connector. For a connection reused
+/// from a `Pool`, there is no DNS, connect or TLS phase.
+/// When redirects were followed, the phases are those of the last request,
+/// while `ttfb` and `total` count from the start of the first.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Timing {
+    /// Looking for an idle connection in the `Pool`.
+    pub pool: Option<Duration>,
+    /// Resolving the host name to addresses.
+    pub dns: Option<Duration>,
+    /// Establishing the TCP connection.
+    pub connect: Option<Duration>,
+    /// The TLS handshake.
+    pub tls: Option<Duration>,
+    /// Writing the request head and body.
+    pub write: Option<Duration>,
+    /// From the start of sending until the response head was received.
+    pub ttfb: Option<Duration>,
+    /// From the start of sending until the body was read to the end.
+    pub total: Option<Duration>,
+}
+
+struct TimingCallback(Arc<Fn(&Url, &Timing) + Send + Sync>);
+
+impl fmt::Debug for TimingCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("TimingCallback")
+    }
+}
+
+impl ClientResponse for Response {
+    fn set_timing(&mut self, timing: Timing, start_ns: u64,
+                  callback: Option<Arc<Fn(&Url, &Timing) + Send + Sync>>) {
+        self.timing = timing;
+        self.timing_start = Some(start_ns);
+        self.on_timing = callback.map(TimingCallback);
+    }
+
+    fn set_har(&mut self, recorder: HarRecorder, mut entry: HarEntry) {
+        entry.status = self.status_raw.0;
+        entry.reason = self.status_raw.1.to_string();
+        entry.version = self.version;
+        entry.response_headers = self.headers.clone();
+        self.har = Some((recorder, entry));
+    }
 }
 
 impl Response {
@@ -54,6 +127,12 @@ impl Response {
             url: url,
             status_raw: raw_status,
             message: message,
+            abort: None,
+            drain_limit: DEFAULT_DRAIN_LIMIT,
+            timing: Timing::default(),
+            timing_start: None,
+            on_timing: None,
+            har: None,
         })
     }
 
@@ -62,17 +141,321 @@ impl Response {
     pub fn status_raw(&self) -> &RawStatus {
         &self.status_raw
     }
+
+    /// Get the remote address of the connection this response was received on.
+    pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.message.peer_addr()
+    }
+
+    /// Whether this response was received on an idle connection reused from
+    /// a `Pool`, rather than a new one.
+    pub fn is_reused(&self) -> bool {
+        self.message.is_reused()
+    }
+
+    /// Get the TLS version, cipher and peer certificates of the connection,
+    /// if this response was received over TLS.
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        self.message.tls_info()
+    }
+
+    /// Get how long each phase of the request took.
+    ///
+    /// Only responses to requests sent by a `Client` are timed. The `total`
+    /// is only known once the body has been read to the end.
+    pub fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    /// Set how many unread bytes of the body may be discarded when this
+    /// response is dropped, to keep the connection alive.
+    ///
+    /// If more of the body is left, the connection is closed instead.
+    /// Requests sent by a `Client` use the client's drain limit.
+    pub fn set_drain_limit(&mut self, limit: u64) {
+        self.drain_limit = limit;
+    }
+
+    /// Let `handle` abort reading this response from another thread.
+    ///
+    /// Requests sent with `RequestBuilder::abortable` already have their
+    /// handle set. Returns `Error::Aborted` if the handle was already aborted.
+    pub fn set_abort_handle(&mut self, handle: AbortHandle) -> ::Result<()> {
+        let result = handle.register(self.message.closer());
+        self.abort = Some(handle);
+        result
+    }
+
+    /// Wrap this response in a reader that reports the progress of reading
+    /// the body, for a progress bar.
+    ///
+    /// The callback receives the bytes read so far, and the `Content-Length`
+    /// of the body, if the server sent one. It is called after each read.
+    pub fn progress<F: FnMut(u64, Option<u64>)>(self, callback: F) -> Progress<F> {
+        let total = self.headers.get::<header::ContentLength>().map(|len| **len);
+        Progress {
+            res: self,
+            read: 0,
+            total: total,
+            callback: callback,
+        }
+    }
+
+    /// Read the whole body, failing with `Error::TooLarge` if it is longer
+    /// than `max` bytes.
+    ///
+    /// The connection isn't reused after such an error, since the rest of the
+    /// body is left unread.
+    pub fn bytes_limited(&mut self, max: u64) -> ::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        if let Err(e) = self.by_ref().take(max.saturating_add(1)).read_to_end(&mut body) {
+            if self.is_aborted() {
+                return Err(::Error::Aborted);
+            }
+            return Err(::Error::Io(e));
+        }
+        if body.len() as u64 > max {
+            debug!("body is longer than {} bytes", max);
+            return Err(::Error::TooLarge);
+        }
+        Ok(body)
+    }
+
+    /// Read the whole body as text, decoded with the `charset` of the
+    /// `Content-Type` header.
+    ///
+    /// UTF-8, UTF-16, US-ASCII and ISO-8859-1 are supported. Without a
+    /// `charset`, the body is decoded as UTF-8.
+    pub fn text(&mut self) -> ::Result<String> {
+        self.text_limited(u64::MAX)
+    }
+
+    /// Like `text()`, but fails with `Error::TooLarge` if the body is longer
+    /// than `max` bytes.
+    pub fn text_limited(&mut self, max: u64) -> ::Result<String> {
+        let charset = match self.headers.get::<header::ContentType>() {
+            Some(&header::ContentType(Mime(_, _, ref params))) => {
+                params.iter().find(|&&(ref attr, _)| *attr == Attr::Charset).map(|p| match p.1 {
+                    Value::Utf8 => Charset::Ext("UTF-8".to_owned()),
+                    Value::Ext(ref s) => s.parse().unwrap(),
+                })
+            },
+            None => None
+        };
+        let body = try!(self.bytes_limited(max));
+        decode(body, charset)
+    }
+
+    /// Read the whole body, and decode it from JSON.
+    ///
+    /// Requires the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn json<T: Decodable>(&mut self) -> ::Result<T> {
+        let text = try!(self.text());
+        json::decode(&text).map_err(|e| {
+            ::Error::Io(io::Error::new(io::ErrorKind::InvalidInput, e))
+        })
+    }
+
+    /// Get the trailers sent after a chunked body.
+    ///
+    /// These are only available once the body has been read to the end, and
+    /// servers usually only send them if the request had a `TE: trailers`
+    /// header.
+    #[inline]
+    pub fn trailers(&self) -> Option<&header::Headers> {
+        self.message.trailers()
+    }
+
+    /// Read the next whole chunk of a chunked body, with its extensions.
+    ///
+    /// See `HttpReader::read_chunk`.
+    pub fn read_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        let result = self.message.read_chunk();
+        if self.is_aborted() {
+            let _ = self.message.close_connection();
+            return Err(aborted_io_error());
+        }
+        match result {
+            Err(e) => {
+                let _ = self.message.close_connection();
+                Err(e)
+            }
+            Ok(None) => {
+                self.finish_timing();
+                Ok(None)
+            }
+            Ok(Some(chunk)) => {
+                self.capture(&chunk.data);
+                Ok(Some(chunk))
+            }
+        }
+    }
+
+    fn finish_timing(&mut self) {
+        if let (None, Some(start)) = (self.timing.total, self.timing_start) {
+            self.timing.total = Some(ns_to_duration(precise_time_ns() - start));
+        }
+    }
+
+    // Reads and discards the rest of the body, like `HttpMessage::drain`. A
+    // recorded body is read through `read` instead, so that the drained
+    // bytes are counted in its HAR entry.
+    fn drain(&mut self) -> io::Result<bool> {
+        if self.har.is_some() {
+            if !self.message.can_drain(self.drain_limit) {
+                return Ok(false);
+            }
+            let limit = self.drain_limit.saturating_add(1);
+            try!(io::copy(&mut self.by_ref().take(limit), &mut io::sink()));
+            self.message.drain(0)
+        } else {
+            self.message.drain(self.drain_limit)
+        }
+    }
+
+    fn capture(&mut self, data: &[u8]) {
+        if let Some((ref recorder, ref mut entry)) = self.har {
+            capture_body(&mut entry.response_body, &mut entry.response_body_size,
+                         data, recorder.body_limit());
+        }
+    }
+}
+
+/// A `Response` that reports the progress of reading its body.
+///
+/// Created by `Response::progress`.
+pub struct Progress<F: FnMut(u64, Option<u64>)> {
+    res: Response,
+    read: u64,
+    total: Option<u64>,
+    callback: F,
+}
+
+impl<F: FnMut(u64, Option<u64>)> Progress<F> {
+    /// Get a reference to the wrapped `Response`.
+    #[inline]
+    pub fn get_ref(&self) -> &Response {
+        &self.res
+    }
+
+    /// Unwrap the `Response`.
+    #[inline]
+    pub fn into_inner(self) -> Response {
+        self.res
+    }
+}
+
+impl<F: FnMut(u64, Option<u64>)> Read for Progress<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = try!(self.res.read(buf));
+        if n > 0 {
+            self.read += n as u64;
+            (self.callback)(self.read, self.total);
+        }
+        Ok(n)
+    }
+}
+
+impl<F: FnMut(u64, Option<u64>)> fmt::Debug for Progress<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("res", &self.res)
+            .field("read", &self.read)
+            .field("total", &self.total)
+            .finish()
+    }
+}
+
+fn decode(body: Vec<u8>, charset: Option<Charset>) -> ::Result<String> {
+    let name = match charset {
+        None => return decode_utf8(body),
+        Some(Charset::Us_Ascii) => return decode_ascii(body),
+        Some(Charset::Iso_8859_1) => return Ok(body.iter().map(|&b| b as char).collect()),
+        Some(Charset::Ext(name)) => name,
+        Some(charset) => return Err(unsupported(&charset.to_string())),
+    };
+    match &name[..] {
+        "UTF-8" | "UTF8" => decode_utf8(body),
+        "LATIN1" | "ISO_8859-1" | "ISO8859-1" => Ok(body.iter().map(|&b| b as char).collect()),
+        // Without a BOM, UTF-16 is big-endian.
+        "UTF-16" if body.starts_with(&[0xFF, 0xFE]) => decode_utf16(&body[2..], false),
+        "UTF-16" if body.starts_with(&[0xFE, 0xFF]) => decode_utf16(&body[2..], true),
+        "UTF-16" | "UTF-16BE" => decode_utf16(&body, true),
+        "UTF-16LE" => decode_utf16(&body, false),
+        _ => Err(unsupported(&name))
+    }
+}
+
+fn decode_ascii(body: Vec<u8>) -> ::Result<String> {
+    if body.iter().any(|&b| b >= 0x80) {
+        return Err(::Error::Io(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "Invalid US-ASCII body")));
+    }
+    Ok(body.into_iter().map(|b| b as char).collect())
+}
+
+fn decode_utf8(body: Vec<u8>) -> ::Result<String> {
+    let body = if body.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        body[3..].to_vec()
+    } else {
+        body
+    };
+    Ok(try!(String::from_utf8(body)))
+}
+
+fn decode_utf16(body: &[u8], big_endian: bool) -> ::Result<String> {
+    if body.len() % 2 != 0 {
+        return Err(::Error::Io(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "UTF-16 body has an odd length")));
+    }
+    let units = body.chunks(2).map(|pair| {
+        if big_endian {
+            (pair[0] as u16) << 8 | pair[1] as u16
+        } else {
+            (pair[1] as u16) << 8 | pair[0] as u16
+        }
+    }).collect::<Vec<u16>>();
+    String::from_utf16(&units).map_err(|_| {
+        ::Error::Io(io::Error::new(io::ErrorKind::InvalidInput, "Invalid UTF-16 body"))
+    })
+}
+
+fn unsupported(charset: &str) -> ::Error {
+    debug!("unsupported charset {:?}", charset);
+    ::Error::Io(io::Error::new(io::ErrorKind::InvalidInput,
+                               format!("Unsupported charset {}", charset)))
+}
+
+impl Response {
+    fn is_aborted(&self) -> bool {
+        self.abort.as_ref().map_or(false, |abort| abort.is_aborted())
+    }
 }
 
 impl Read for Response {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        match self.message.read(buf) {
+        let result = self.message.read(buf);
+        // Shutting down the connection usually looks like EOF to a blocked
+        // read, so an aborted response must not be mistaken for a whole one.
+        if self.is_aborted() {
+            let _ = self.message.close_connection();
+            return Err(aborted_io_error());
+        }
+        match result {
             Err(e) => {
                 let _ = self.message.close_connection();
                 Err(e)
             }
-            r => r
+            Ok(0) if !buf.is_empty() => {
+                self.finish_timing();
+                Ok(0)
+            }
+            Ok(n) => {
+                self.capture(&buf[..n]);
+                Ok(n)
+            }
         }
     }
 }
@@ -80,13 +463,33 @@ impl Read for Response {
 impl Drop for Response {
     fn drop(&mut self) {
         // if not drained, theres old bits in the Reader. we can't reuse this,
-        // since those old bits would end up in new Responses
+        // since those old bits would end up in new Responses. small leftovers
+        // are read and thrown away, as that's cheaper than a new connection.
         //
         // otherwise, the response has been drained. we should check that the
         // server has agreed to keep the connection open
-        let is_drained = !self.message.has_body();
+        let is_aborted = match self.abort {
+            Some(ref abort) => abort.unregister(),
+            None => false
+        };
+        let keep_alive = !is_aborted && http::should_keep_alive(self.version, &self.headers);
+        let is_drained = keep_alive && match self.drain() {
+            Ok(drained) => drained,
+            Err(e) => {
+                debug!("Response.drop error draining body: {}", e);
+                false
+            }
+        };
+
+        if let Some(TimingCallback(ref callback)) = self.on_timing {
+            callback(&self.url, &self.timing);
+        }
+        if let Some((recorder, mut entry)) = self.har.take() {
+            entry.timing = self.timing;
+            recorder.record(entry);
+        }
         trace!("Response.drop is_drained={}", is_drained);
-        if !(is_drained && http::should_keep_alive(self.version, &self.headers)) {
+        if !is_drained {
             trace!("Response.drop closing connection");
             if let Err(e) = self.message.close_connection() {
                 error!("Response.drop error closing connection: {}", e);
@@ -104,7 +507,7 @@ mod tests {
     use header::TransferEncoding;
     use header::Encoding;
     use http::HttpMessage;
-    use mock::MockStream;
+    use testing::MockStream;
     use status;
     use version;
     use http::h1::Http11Message;
@@ -161,6 +564,56 @@ mod tests {
         assert_eq!(read_to_string(res).unwrap(), "qwert".to_owned());
     }
 
+    #[test]
+    fn test_parse_chunked_response_trailers() {
+        let stream = MockStream::with_input(b"\
+            HTTP/1.1 200 OK\r\n\
+            Transfer-Encoding: chunked\r\n\
+            Trailer: Content-MD5\r\n\
+            \r\n\
+            1\r\n\
+            q\r\n\
+            0\r\n\
+            Content-MD5: abc\r\n\
+            \r\n"
+        );
+
+        let url = Url::parse("http://hyper.rs").unwrap();
+        let mut res = Response::new(url, Box::new(stream)).unwrap();
+        assert!(res.trailers().is_none());
+
+        let mut s = String::new();
+        res.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "q");
+        assert_eq!(res.trailers().unwrap().get_raw("Content-MD5").unwrap(),
+                   &[b"abc".to_vec()][..]);
+    }
+
+    #[test]
+    fn test_read_chunk() {
+        use http::Chunk;
+        let stream = MockStream::with_input(b"\
+            HTTP/1.1 200 OK\r\n\
+            Transfer-Encoding: chunked\r\n\
+            \r\n\
+            1;id=1\r\n\
+            q\r\n\
+            0\r\n\
+            Content-MD5: abc\r\n\
+            \r\n"
+        );
+
+        let url = Url::parse("http://hyper.rs").unwrap();
+        let mut res = Response::new(url, Box::new(stream)).unwrap();
+        assert_eq!(res.read_chunk().unwrap(), Some(Chunk {
+            extensions: vec![("id".to_owned(), Some("1".to_owned()))],
+            data: b"q".to_vec(),
+        }));
+        assert_eq!(res.read_chunk().unwrap(), None);
+        assert_eq!(res.trailers().unwrap().get_raw("Content-MD5").unwrap(),
+                   &[b"abc".to_vec()][..]);
+    }
+
     /// Tests that when a chunk size is not a valid radix-16 number, an error
     /// is returned.
     #[test]
@@ -221,6 +674,16 @@ mod tests {
         assert_eq!(read_to_string(res).unwrap(), "1".to_owned());
     }
 
+    #[test]
+    fn test_connection_info() {
+        let url = Url::parse("http://hyper.rs").unwrap();
+        let stream = MockStream::with_input(b"HTTP/1.1 200 OK\r\n\r\n");
+        let mut res = Response::new(url, Box::new(stream)).unwrap();
+        assert_eq!(res.peer_addr().unwrap(), "127.0.0.1:1337".parse().unwrap());
+        assert!(!res.is_reused());
+        assert!(res.tls_info().is_none());
+    }
+
     #[test]
     fn test_parse_error_closes() {
         let url = Url::parse("http://hyper.rs").unwrap();
@@ -230,4 +693,71 @@ mod tests {
 
         assert!(Response::new(url, Box::new(stream)).is_err());
     }
+
+    fn response(head: &str, body: &[u8]) -> Response {
+        let mut input = head.as_bytes().to_vec();
+        input.extend(body.iter().cloned());
+        let url = Url::parse("http://hyper.rs").unwrap();
+        Response::new(url, Box::new(MockStream::with_input(&input))).unwrap()
+    }
+
+    #[test]
+    fn test_bytes_limited() {
+        let head = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n";
+        assert_eq!(response(head, b"hello").bytes_limited(5).unwrap(), b"hello");
+        match response(head, b"hello").bytes_limited(4) {
+            Err(::Error::TooLarge) => (),
+            other => panic!("expected TooLarge, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_text_charsets() {
+        fn text(content_type: &str, body: &[u8]) -> ::Result<String> {
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n{}\r\n",
+                               body.len(), content_type);
+            response(&head, body).text()
+        }
+
+        assert_eq!(text("", "h\u{e9}".as_bytes()).unwrap(), "h\u{e9}");
+        assert_eq!(text("Content-Type: text/plain; charset=utf-8\r\n", b"\xEF\xBB\xBFhi").unwrap(),
+                   "hi");
+        assert_eq!(text("Content-Type: text/plain; charset=ISO-8859-1\r\n", b"h\xE9").unwrap(),
+                   "h\u{e9}");
+        assert_eq!(text("Content-Type: text/plain; charset=utf-16\r\n", b"\xFF\xFEh\x00i\x00")
+                   .unwrap(), "hi");
+        assert_eq!(text("Content-Type: text/plain; charset=utf-16\r\n", b"\x00h\x00i").unwrap(),
+                   "hi");
+        assert_eq!(text("Content-Type: text/plain; charset=UTF-16LE\r\n", b"h\x00i\x00").unwrap(),
+                   "hi");
+        assert!(text("", b"\xFF").is_err());
+        assert_eq!(text("Content-Type: text/plain; charset=us-ascii\r\n", b"hi").unwrap(),
+                   "hi");
+        assert!(text("Content-Type: text/plain; charset=us-ascii\r\n", b"h\xE9").is_err());
+        assert!(text("Content-Type: text/plain; charset=utf-16\r\n", b"h").is_err());
+        assert!(text("Content-Type: text/plain; charset=KOI8-R\r\n", b"hi").is_err());
+    }
+
+    #[test]
+    fn test_download_progress() {
+        let head = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n";
+        let mut calls = Vec::new();
+        {
+            let mut res = response(head, b"hello").progress(|read, total| calls.push((read, total)));
+            let mut buf = [0; 3];
+            assert_eq!(res.read(&mut buf).unwrap(), 3);
+            assert_eq!(res.read(&mut buf).unwrap(), 2);
+            assert_eq!(res.read(&mut buf).unwrap(), 0);
+        }
+        assert_eq!(calls, vec![(3, Some(5)), (5, Some(5))]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let head = "HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\n";
+        let v: Vec<u32> = response(head, b"[1,2,3]").json().unwrap();
+        assert_eq!(v, vec![1, 2, 3]);
+        assert!(response(head, b"[1,2,}").json::<Vec<u32>>().is_err());
+    }
 }
@@ -22,7 +22,9 @@ use self::Error::{
     Ssl,
     TooLarge,
     Http2,
-    Utf8
+    Utf8,
+    Aborted,
+    CircuitOpen
 };
 
 
@@ -40,7 +42,8 @@ pub enum Error {
     Version,
     /// An invalid `Header`.
     Header,
-    /// A message head is too large to be reasonable.
+    /// A message head is too large to be reasonable, or a body is longer than
+    /// the limit it was read with.
     TooLarge,
     /// An invalid `Status`, such as `1337 ELITE`.
     Status,
@@ -52,6 +55,10 @@ pub enum Error {
     Http2(Http2Error),
     /// Parsing a field as string failed
     Utf8(Utf8Error),
+    /// The request was cancelled with an `AbortHandle`.
+    Aborted,
+    /// The request was not sent, as the `CircuitBreaker` of its host is open.
+    CircuitOpen,
 
     #[doc(hidden)]
     __Nonexhaustive(Void)
@@ -78,13 +85,15 @@ impl StdError for Error {
             Method => "Invalid Method specified",
             Version => "Invalid HTTP version specified",
             Header => "Invalid Header provided",
-            TooLarge => "Message head is too large",
+            TooLarge => "Message head or body is too large",
             Status => "Invalid Status provided",
             Uri(ref e) => e.description(),
             Io(ref e) => e.description(),
             Ssl(ref e) => e.description(),
             Http2(ref e) => e.description(),
             Utf8(ref e) => e.description(),
+            Aborted => "Request aborted",
+            CircuitOpen => "Circuit breaker is open",
             Error::__Nonexhaustive(ref void) =>  match *void {}
         }
     }
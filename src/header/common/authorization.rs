@@ -2,8 +2,10 @@ use std::any::Any;
 use std::fmt::{self, Display};
 use std::str::{FromStr, from_utf8};
 use std::ops::{Deref, DerefMut};
+use url::percent_encoding::lossy_utf8_percent_decode;
 use serialize::base64::{ToBase64, FromBase64, Standard, Config, Newline};
 use header::{Header, HeaderFormat};
+use uri::percent_encode;
 
 /// `Authorization` header, defined in [RFC7235](https://tools.ietf.org/html/rfc7235#section-4.2)
 ///
@@ -54,6 +56,23 @@ use header::{Header, HeaderFormat};
 ///    )
 /// );
 /// ```
+/// ```
+/// use hyper::header::{Headers, Authorization, OAuth1};
+///
+/// let mut headers = Headers::new();
+/// headers.set(
+///    Authorization(
+///        OAuth1 {
+///            realm: None,
+///            params: vec![
+///                ("oauth_consumer_key".to_owned(), "dpf43f3p2l4k3l03".to_owned()),
+///                ("oauth_signature".to_owned(), "kd94hf93k423kf44&".to_owned()),
+///                ("oauth_signature_method".to_owned(), "PLAINTEXT".to_owned()),
+///            ]
+///        }
+///    )
+/// );
+/// ```
 #[derive(Clone, PartialEq, Debug)]
 pub struct Authorization<S: Scheme>(pub S);
 
@@ -218,9 +237,118 @@ impl FromStr for Bearer {
 	}
 }
 
+/// Credential holder for OAuth 1.0 Authentication, defined in
+/// [RFC5849](https://tools.ietf.org/html/rfc5849#section-3.5.1)
+///
+/// The parameters are formatted in order, after the realm, with their values
+/// percent-encoded and quoted. The realm is a quoted-string, with `"` and `\`
+/// escaped by a backslash. `client::oauth1::OAuth1Signer` builds them
+/// for a request.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OAuth1 {
+    /// The realm of the protection space, if any.
+    pub realm: Option<String>,
+    /// The `oauth_*` parameters, with their values decoded.
+    pub params: Vec<(String, String)>
+}
+
+impl OAuth1 {
+    /// Get the value of a parameter.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params.iter()
+            .find(|&&(ref param, _)| param == name)
+            .map(|&(_, ref value)| &value[..])
+    }
+}
+
+impl Scheme for OAuth1 {
+    fn scheme() -> Option<&'static str> {
+        Some("OAuth")
+    }
+
+    fn fmt_scheme(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        if let Some(ref realm) = self.realm {
+            try!(f.write_str("realm=\""));
+            for c in realm.chars() {
+                if c == '"' || c == '\\' {
+                    try!(f.write_str("\\"));
+                }
+                try!(write!(f, "{}", c));
+            }
+            try!(f.write_str("\""));
+            first = false;
+        }
+        for &(ref name, ref value) in &self.params {
+            if !first {
+                try!(f.write_str(", "));
+            }
+            first = false;
+            try!(write!(f, "{}=\"{}\"", percent_encode(name.as_bytes()),
+                        percent_encode(value.as_bytes())));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OAuth1 {
+    type Err = ::Error;
+    fn from_str(s: &str) -> ::Result<OAuth1> {
+        let mut oauth = OAuth1 {
+            realm: None,
+            params: Vec::new()
+        };
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            let eq = match rest.find('=') {
+                Some(eq) => eq,
+                None => return Err(::Error::Header)
+            };
+            let name = rest[..eq].trim();
+            let value = &rest[eq + 1..];
+            if !value.starts_with('"') {
+                return Err(::Error::Header);
+            }
+            let mut quoted = String::new();
+            let mut escaped = false;
+            let mut end = None;
+            for (i, c) in value.char_indices().skip(1) {
+                if escaped {
+                    quoted.push(c);
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    end = Some(i);
+                    break;
+                } else {
+                    quoted.push(c);
+                }
+            }
+            let end = match end {
+                Some(end) => end,
+                None => return Err(::Error::Header)
+            };
+            if name == "realm" {
+                oauth.realm = Some(quoted);
+            } else {
+                oauth.params.push((lossy_utf8_percent_decode(name.as_bytes()),
+                                   lossy_utf8_percent_decode(quoted.as_bytes())));
+            }
+            rest = value[end + 1..].trim_left();
+            if rest.starts_with(',') {
+                rest = rest[1..].trim_left();
+            } else if !rest.is_empty() {
+                return Err(::Error::Header);
+            }
+        }
+        Ok(oauth)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Authorization, Basic, Bearer};
+    use super::{Authorization, Basic, Bearer, OAuth1};
     use super::super::super::{Headers, Header};
 
     #[test]
@@ -286,8 +414,58 @@ mod tests {
             &[b"Bearer fpKL54jvWmEGVoRdCNjG".to_vec()]).unwrap();
         assert_eq!(auth.0.token, "fpKL54jvWmEGVoRdCNjG");
     }
+
+    #[test]
+    fn test_oauth1_auth() {
+        let mut headers = Headers::new();
+        headers.set(Authorization(OAuth1 {
+            realm: Some("Example".to_owned()),
+            params: vec![
+                ("oauth_consumer_key".to_owned(), "dpf43f3p2l4k3l03".to_owned()),
+                ("oauth_signature".to_owned(), "tR3+Ty81lMeYAr/Fid0kMTYa/WM=".to_owned()),
+                ("oauth_version".to_owned(), "1.0".to_owned()),
+            ]
+        }));
+        assert_eq!(
+            headers.to_string(),
+            "Authorization: OAuth realm=\"Example\", oauth_consumer_key=\"dpf43f3p2l4k3l03\", \
+             oauth_signature=\"tR3%2BTy81lMeYAr%2FFid0kMTYa%2FWM%3D\", \
+             oauth_version=\"1.0\"\r\n".to_owned());
+    }
+
+    #[test]
+    fn test_oauth1_auth_parse() {
+        let auth: Authorization<OAuth1> = Header::parse_header(
+            &[b"OAuth realm=\"Photos, Inc\", oauth_signature=\"tR3%2BTy81lMeYAr%2FFid0kMTYa%2FWM%3D\",\
+                oauth_version=\"1.0\"".to_vec()]).unwrap();
+        assert_eq!(auth.0.realm, Some("Photos, Inc".to_owned()));
+        assert_eq!(auth.0.get("oauth_signature"), Some("tR3+Ty81lMeYAr/Fid0kMTYa/WM="));
+        assert_eq!(auth.0.get("oauth_version"), Some("1.0"));
+        let unquoted: ::Result<Authorization<OAuth1>> = Header::parse_header(
+            &[b"OAuth oauth_version=1.0".to_vec()]);
+        assert!(unquoted.is_err());
+    }
+
+    #[test]
+    fn test_oauth1_auth_realm_escaped() {
+        let oauth = OAuth1 {
+            realm: Some("The \"Photos\" \\ Inc".to_owned()),
+            params: vec![("oauth_version".to_owned(), "1.0".to_owned())]
+        };
+        let mut headers = Headers::new();
+        headers.set(Authorization(oauth.clone()));
+        assert_eq!(
+            headers.to_string(),
+            "Authorization: OAuth realm=\"The \\\"Photos\\\" \\\\ Inc\", \
+             oauth_version=\"1.0\"\r\n".to_owned());
+
+        let auth: Authorization<OAuth1> = Header::parse_header(
+            &[headers.get_raw("Authorization").unwrap()[0].clone()]).unwrap();
+        assert_eq!(auth.0, oauth);
+    }
 }
 
 bench_header!(raw, Authorization<String>, { vec![b"foo bar baz".to_vec()] });
 bench_header!(basic, Authorization<Basic>, { vec![b"Basic QWxhZGRpbjpuIHNlc2FtZQ==".to_vec()] });
 bench_header!(bearer, Authorization<Bearer>, { vec![b"Bearer fpKL54jvWmEGVoRdCNjG".to_vec()] });
+bench_header!(oauth1, Authorization<OAuth1>, { vec![b"OAuth oauth_version=\"1.0\"".to_vec()] });
@@ -3,8 +3,7 @@ use std::borrow::Cow;
 use std::cmp::min;
 use std::fmt;
 use std::io::{self, Write, BufWriter, BufRead, Read};
-use std::net::Shutdown;
-#[cfg(feature = "timeouts")]
+use std::net::{Shutdown, SocketAddr};
 use std::time::Duration;
 
 use httparse;
@@ -14,7 +13,7 @@ use Error;
 use header::{Headers, ContentLength, TransferEncoding};
 use header::Encoding::Chunked;
 use method::{Method};
-use net::{NetworkConnector, NetworkStream};
+use net::{ConnectTiming, NetworkConnector, NetworkStream, StreamCloser, TlsInfo};
 use status::StatusCode;
 use version::HttpVersion;
 use version::HttpVersion::{Http10, Http11};
@@ -24,13 +23,13 @@ use self::HttpReader::{SizedReader, ChunkedReader, EofReader, EmptyReader};
 use self::HttpWriter::{ChunkedWriter, SizedWriter, EmptyWriter, ThroughWriter};
 
 use http::{
+    Chunk,
     RawStatus,
     Protocol,
     HttpMessage,
     RequestHead,
     ResponseHead,
 };
-use header;
 use version;
 
 const MAX_INVALID_RESPONSE_BYTES: usize = 1024 * 128;
@@ -60,7 +59,7 @@ impl<T> Wrapper<T> {
 enum Stream {
     Idle(Box<NetworkStream + Send>),
     Writing(HttpWriter<BufWriter<Box<NetworkStream + Send>>>),
-    Reading(HttpReader<BufReader<Box<NetworkStream + Send>>>),
+    Reading(TrailersReader<BufReader<Box<NetworkStream + Send>>>),
 }
 
 impl Stream {
@@ -70,13 +69,13 @@ impl Stream {
             _ => None,
         }
     }
-    fn reader_mut(&mut self) -> Option<&mut HttpReader<BufReader<Box<NetworkStream + Send>>>> {
+    fn reader_mut(&mut self) -> Option<&mut TrailersReader<BufReader<Box<NetworkStream + Send>>>> {
         match *self {
             Stream::Reading(ref mut reader) => Some(reader),
             _ => None,
         }
     }
-    fn reader_ref(&self) -> Option<&HttpReader<BufReader<Box<NetworkStream + Send>>>> {
+    fn reader_ref(&self) -> Option<&TrailersReader<BufReader<Box<NetworkStream + Send>>>> {
         match *self {
             Stream::Reading(ref reader) => Some(reader),
             _ => None,
@@ -93,6 +92,12 @@ impl Stream {
 pub struct Http11Message {
     method: Option<Method>,
     stream: Wrapper<Stream>,
+    // a final response received while waiting for `100 Continue`
+    early_response: Option<ResponseHead>,
+    // trailers to write after a chunked body
+    trailers: Option<Headers>,
+    // whether the request is sent to a proxy, which needs the absolute URL
+    proxied: bool,
 }
 
 impl Write for Http11Message {
@@ -126,11 +131,12 @@ impl Read for Http11Message {
 }
 
 impl HttpMessage for Http11Message {
-    fn set_outgoing(&mut self, mut head: RequestHead) -> ::Result<RequestHead> {
+    fn set_outgoing(&mut self, head: RequestHead) -> ::Result<RequestHead> {
         let mut res = Err(Error::from(io::Error::new(
                             io::ErrorKind::Other,
                             "")));
         let mut method = None;
+        let proxied = self.proxied;
         self.stream.map_in_place(|stream: Stream| -> Stream {
             let stream = match stream {
                 Stream::Idle(stream) => stream,
@@ -141,97 +147,20 @@ impl HttpMessage for Http11Message {
                     return stream;
                 },
             };
-            let mut stream = BufWriter::new(stream);
-
-            let mut uri = head.url.serialize_path().unwrap();
-            if let Some(ref q) = head.url.query {
-                uri.push('?');
-                uri.push_str(&q[..]);
-            }
-
-            let version = version::HttpVersion::Http11;
-            debug!("request line: {:?} {:?} {:?}", head.method, uri, version);
-            match write!(&mut stream, "{} {} {}{}",
-                         head.method, uri, version, LINE_ENDING) {
-                             Err(e) => {
-                                 res = Err(From::from(e));
-                                 // TODO What should we do if the BufWriter doesn't wanna
-                                 // relinquish the stream?
-                                 return Stream::Idle(stream.into_inner().ok().unwrap());
-                             },
-                             Ok(_) => {},
-                         };
-
-            let stream = {
-                let write_headers = |mut stream: BufWriter<Box<NetworkStream + Send>>, head: &RequestHead| {
-                    debug!("headers={:?}", head.headers);
-                    match write!(&mut stream, "{}{}", head.headers, LINE_ENDING) {
-                        Ok(_) => Ok(stream),
-                        Err(e) => {
-                            Err((e, stream.into_inner().unwrap()))
-                        }
-                    }
-                };
-                match &head.method {
-                    &Method::Get | &Method::Head => {
-                        let writer = match write_headers(stream, &head) {
-                            Ok(w) => w,
-                            Err(e) => {
-                                res = Err(From::from(e.0));
-                                return Stream::Idle(e.1);
-                            }
-                        };
-                        EmptyWriter(writer)
-                    },
-                    _ => {
-                        let mut chunked = true;
-                        let mut len = 0;
-
-                        match head.headers.get::<header::ContentLength>() {
-                            Some(cl) => {
-                                chunked = false;
-                                len = **cl;
-                            },
-                            None => ()
-                        };
-
-                        // can't do in match above, thanks borrowck
-                        if chunked {
-                            let encodings = match head.headers.get_mut::<header::TransferEncoding>() {
-                                Some(encodings) => {
-                                    //TODO: check if chunked is already in encodings. use HashSet?
-                                    encodings.push(header::Encoding::Chunked);
-                                    false
-                                },
-                                None => true
-                            };
-
-                            if encodings {
-                                head.headers.set(
-                                    header::TransferEncoding(vec![header::Encoding::Chunked]))
-                            }
-                        }
 
-                        let stream = match write_headers(stream, &head) {
-                            Ok(s) => s,
-                            Err(e) => {
-                                res = Err(From::from(e.0));
-                                return Stream::Idle(e.1);
-                            },
-                        };
-
-                        if chunked {
-                            ChunkedWriter(stream)
-                        } else {
-                            SizedWriter(stream, len)
-                        }
-                    }
+            match start_request(BufWriter::new(stream), head, proxied) {
+                Ok((writer, head)) => {
+                    method = Some(head.method.clone());
+                    res = Ok(head);
+                    Stream::Writing(writer)
+                },
+                Err((e, stream)) => {
+                    res = Err(From::from(e));
+                    // TODO What should we do if the BufWriter doesn't wanna
+                    // relinquish the stream?
+                    Stream::Idle(stream.into_inner().ok().unwrap())
                 }
-            };
-
-            method = Some(head.method.clone());
-            res = Ok(head);
-            Stream::Writing(stream)
+            }
         });
 
         self.method = method;
@@ -241,6 +170,10 @@ impl HttpMessage for Http11Message {
     fn get_incoming(&mut self) -> ::Result<ResponseHead> {
         try!(self.flush_outgoing());
         let method = self.method.take().unwrap_or(Method::Get);
+        if let Some(head) = self.early_response.take() {
+            trace!("early response already read");
+            return Ok(head);
+        }
         let mut res = Err(From::from(
                         io::Error::new(io::ErrorKind::Other,
                         "Read already in progress")));
@@ -282,57 +215,114 @@ impl HttpMessage for Http11Message {
                 break;
             }
 
-            let raw_status = head.subject;
-            let headers = head.headers;
-
-            let is_empty = !should_have_response_body(&method, raw_status.0);
+            let is_empty = !should_have_response_body(&method, head.subject.0);
             stream.get_mut().set_previous_response_expected_no_content(is_empty);
-            // According to https://tools.ietf.org/html/rfc7230#section-3.3.3
-            // 1. HEAD reponses, and Status 1xx, 204, and 304 cannot have a body.
-            // 2. Status 2xx to a CONNECT cannot have a body.
-            // 3. Transfer-Encoding: chunked has a chunked body.
-            // 4. If multiple differing Content-Length headers or invalid, close connection.
-            // 5. Content-Length header has a sized body.
-            // 6. Not Client.
-            // 7. Read till EOF.
-            let reader = if is_empty {
-                EmptyReader(stream)
-            } else {
-                if let Some(&TransferEncoding(ref codings)) = headers.get() {
-                    if codings.last() == Some(&Chunked) {
-                        ChunkedReader(stream, None)
-                    } else {
-                        trace!("not chuncked. read till eof");
-                        EofReader(stream)
-                    }
-                } else if let Some(&ContentLength(len)) =  headers.get() {
-                    SizedReader(stream, len)
-                } else if headers.has::<ContentLength>() {
-                    trace!("illegal Content-Length: {:?}", headers.get_raw("Content-Length"));
-                    res = Err(Error::Header);
+            let reader = match response_reader(&method, &head, stream) {
+                Ok(reader) => reader,
+                Err((e, stream)) => {
+                    res = Err(e);
                     return Stream::Idle(stream.into_inner());
-                } else {
-                    trace!("neither Transfer-Encoding nor Content-Length");
-                    EofReader(stream)
                 }
             };
 
-            trace!("Http11Message.reader = {:?}", reader);
-
-
             res = Ok(ResponseHead {
-                headers: headers,
-                raw_status: raw_status,
+                headers: head.headers,
+                raw_status: head.subject,
                 version: head.version,
             });
 
-            Stream::Reading(reader)
+            Stream::Reading(TrailersReader::new(reader))
         });
         res
     }
 
+    fn wait_continue(&mut self, timeout: Duration) -> ::Result<bool> {
+        if !wait_continue_supported() {
+            return Ok(true);
+        }
+        let method = self.method.clone().unwrap_or(Method::Get);
+        let mut res = Ok(true);
+        let mut early_response = None;
+        self.stream.map_in_place(|stream| {
+            let writer = match stream {
+                Stream::Writing(writer) => writer,
+                _ => return stream
+            };
+            let (mut writer, kind) = split_writer(writer);
+            let raw = match writer.flush() {
+                Ok(()) => writer.into_inner().ok().unwrap(),
+                Err(e) => {
+                    res = Err(From::from(e));
+                    return Stream::Writing(join_writer(writer, kind));
+                }
+            };
+            if let Err(e) = set_continue_timeout(&*raw, timeout) {
+                res = Err(From::from(e));
+                return Stream::Writing(join_writer(BufWriter::new(raw), kind));
+            }
+
+            let mut stream = BufReader::new(raw);
+            let head;
+            loop {
+                let next = match parse_response(&mut stream) {
+                    Ok(next) => next,
+                    Err(Error::Io(ref e)) if stream.get_buf().is_empty() &&
+                            (e.kind() == io::ErrorKind::WouldBlock ||
+                             e.kind() == io::ErrorKind::TimedOut) => {
+                        debug!("no 100 Continue within {:?}, writing body", timeout);
+                        return Stream::Writing(join_writer(BufWriter::new(stream.into_inner()),
+                                                           kind));
+                    },
+                    Err(e) => {
+                        res = Err(e);
+                        return Stream::Idle(stream.into_inner());
+                    }
+                };
+                match next.subject.0 {
+                    100 if stream.get_buf().is_empty() => {
+                        debug!("100 Continue received");
+                        return Stream::Writing(join_writer(BufWriter::new(stream.into_inner()),
+                                                           kind));
+                    },
+                    // any other interim response, or a final response already
+                    // waiting behind the `100 Continue`
+                    100...199 => continue,
+                    _ => head = next
+                }
+                break;
+            }
+            debug!("final response {:?} before writing body", head.subject);
+
+            // The server doesn't want the body, but did read a request head
+            // promising one, so the connection cannot be reused. Shut down
+            // our half, and read the response.
+            if let Err(e) = stream.get_mut().close(Shutdown::Write) {
+                res = Err(From::from(e));
+                return Stream::Idle(stream.into_inner());
+            }
+            let is_empty = !should_have_response_body(&method, head.subject.0);
+            stream.get_mut().set_previous_response_expected_no_content(is_empty);
+            let reader = match response_reader(&method, &head, stream) {
+                Ok(reader) => reader,
+                Err((e, stream)) => {
+                    res = Err(e);
+                    return Stream::Idle(stream.into_inner());
+                }
+            };
+            early_response = Some(ResponseHead {
+                headers: head.headers,
+                raw_status: head.subject,
+                version: head.version,
+            });
+            res = Ok(false);
+            Stream::Reading(TrailersReader::new(reader))
+        });
+        self.early_response = early_response;
+        res
+    }
+
     fn has_body(&self) -> bool {
-        match self.stream.as_ref().reader_ref() {
+        match self.stream.as_ref().reader_ref().map(|reader| reader.get_ref()) {
             Some(&EmptyReader(..)) |
             Some(&SizedReader(_, 0)) |
             Some(&ChunkedReader(_, Some(0))) => false,
@@ -358,6 +348,77 @@ impl HttpMessage for Http11Message {
         try!(self.get_mut().close(Shutdown::Both));
         Ok(())
     }
+
+    #[inline]
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        self.get_ref().closer()
+    }
+
+    #[inline]
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.get_mut().peer_addr()
+    }
+
+    #[inline]
+    fn is_reused(&self) -> bool {
+        self.get_ref().is_reused()
+    }
+
+    #[inline]
+    fn tls_info(&self) -> Option<TlsInfo> {
+        self.get_ref().tls_info()
+    }
+
+    #[inline]
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        self.get_ref().connect_timing()
+    }
+
+    #[inline]
+    fn set_proxied(&mut self, proxied: bool) {
+        self.proxied = proxied;
+    }
+
+    fn drain(&mut self, limit: u64) -> io::Result<bool> {
+        match self.stream.as_mut().reader_mut() {
+            Some(reader) => reader.drain(limit),
+            None => Ok(false)
+        }
+    }
+
+    fn can_drain(&self, limit: u64) -> bool {
+        match self.stream.as_ref().reader_ref() {
+            Some(reader) => reader.can_drain(limit),
+            None => false
+        }
+    }
+
+    #[inline]
+    fn set_trailers(&mut self, trailers: Headers) {
+        self.trailers = Some(trailers);
+    }
+
+    #[inline]
+    fn trailers(&self) -> Option<&Headers> {
+        self.stream.as_ref().reader_ref().and_then(|reader| reader.trailers())
+    }
+
+    fn read_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        match self.stream.as_mut().reader_mut() {
+            None => Err(io::Error::new(io::ErrorKind::Other,
+                                       "Not in a readable state")),
+            Some(reader) => reader.read_chunk(),
+        }
+    }
+
+    fn write_chunk(&mut self, data: &[u8], extensions: &[(&str, Option<&str>)])
+            -> io::Result<()> {
+        match self.stream.as_mut().writer_mut() {
+            None => Err(io::Error::new(io::ErrorKind::Other,
+                                       "Not in a writable state")),
+            Some(writer) => writer.write_chunk(data, extensions),
+        }
+    }
 }
 
 impl Http11Message {
@@ -366,7 +427,7 @@ impl Http11Message {
         match self.stream.into_inner() {
             Stream::Idle(stream) => stream,
             Stream::Writing(stream) => stream.into_inner().into_inner().unwrap(),
-            Stream::Reading(stream) => stream.into_inner().into_inner(),
+            Stream::Reading(stream) => stream.into_inner().into_inner().into_inner(),
         }
     }
 
@@ -376,7 +437,7 @@ impl Http11Message {
         match *self.stream.as_ref() {
             Stream::Idle(ref stream) => &**stream,
             Stream::Writing(ref stream) => &**stream.get_ref().get_ref(),
-            Stream::Reading(ref stream) => &**stream.get_ref().get_ref()
+            Stream::Reading(ref stream) => &**stream.get_ref().get_ref().get_ref()
         }
     }
 
@@ -386,7 +447,7 @@ impl Http11Message {
         match *self.stream.as_mut() {
             Stream::Idle(ref mut stream) => &mut **stream,
             Stream::Writing(ref mut stream) => &mut **stream.get_mut().get_mut(),
-            Stream::Reading(ref mut stream) => &mut **stream.get_mut().get_mut()
+            Stream::Reading(ref mut stream) => &mut **stream.get_mut().get_mut().get_mut()
         }
     }
 
@@ -396,6 +457,9 @@ impl Http11Message {
         Http11Message {
             method: None,
             stream: Wrapper::new(Stream::new(stream)),
+            early_response: None,
+            trailers: None,
+            proxied: false,
         }
     }
 
@@ -404,6 +468,7 @@ impl Http11Message {
     /// TODO It might be sensible to lift this up to the `HttpMessage` trait itself...
     pub fn flush_outgoing(&mut self) -> ::Result<()> {
         let mut res = Ok(());
+        let trailers = self.trailers.take();
         self.stream.map_in_place(|stream| {
             let writer = match stream {
                 Stream::Writing(writer) => writer,
@@ -413,7 +478,11 @@ impl Http11Message {
                 },
             };
             // end() already flushes
-            let raw = match writer.end() {
+            let end = match trailers {
+                Some(ref trailers) => writer.end_with_trailers(trailers),
+                None => writer.end()
+            };
+            let raw = match end {
                 Ok(buf) => buf.into_inner().unwrap(),
                 Err(e) => {
                     res = Err(From::from(e.0));
@@ -426,6 +495,153 @@ impl Http11Message {
     }
 }
 
+/// Picks the reader for the body of a response to a request with `method`,
+/// given the response head.
+///
+/// On error, the stream is handed back, so that it can be closed.
+pub fn response_reader<R: Read>(method: &Method, head: &Incoming<RawStatus>, stream: R)
+        -> Result<HttpReader<R>, (Error, R)> {
+    let headers = &head.headers;
+    let is_empty = !should_have_response_body(method, head.subject.0);
+    // According to https://tools.ietf.org/html/rfc7230#section-3.3.3
+    // 1. HEAD reponses, and Status 1xx, 204, and 304 cannot have a body.
+    // 2. Status 2xx to a CONNECT cannot have a body.
+    // 3. Transfer-Encoding: chunked has a chunked body.
+    // 4. If multiple differing Content-Length headers or invalid, close connection.
+    // 5. Content-Length header has a sized body.
+    // 6. Not Client.
+    // 7. Read till EOF.
+    let reader = if is_empty {
+        EmptyReader(stream)
+    } else {
+        if let Some(&TransferEncoding(ref codings)) = headers.get() {
+            if codings.last() == Some(&Chunked) {
+                ChunkedReader(stream, None)
+            } else {
+                trace!("not chuncked. read till eof");
+                EofReader(stream)
+            }
+        } else if let Some(&ContentLength(len)) =  headers.get() {
+            SizedReader(stream, len)
+        } else if headers.has::<ContentLength>() {
+            trace!("illegal Content-Length: {:?}", headers.get_raw("Content-Length"));
+            return Err((Error::Header, stream));
+        } else {
+            trace!("neither Transfer-Encoding nor Content-Length");
+            EofReader(stream)
+        }
+    };
+
+    trace!("Http11Message.reader = {:?}", reader);
+    Ok(reader)
+}
+
+/// Writes the request line and headers of `head` to `stream`, returning an
+/// `HttpWriter` for the body, and the head as it was sent.
+///
+/// A request that can have a body, but has no `Content-Length`, is made
+/// chunked. A `proxied` request has the absolute URL in its request line.
+pub fn start_request<W: Write>(mut stream: W, mut head: RequestHead, proxied: bool)
+        -> Result<(HttpWriter<W>, RequestHead), (io::Error, W)> {
+    let uri = if proxied {
+        head.url.serialize_no_fragment()
+    } else {
+        let mut uri = head.url.serialize_path().unwrap();
+        if let Some(ref q) = head.url.query {
+            uri.push('?');
+            uri.push_str(&q[..]);
+        }
+        uri
+    };
+
+    let version = version::HttpVersion::Http11;
+    debug!("request line: {:?} {:?} {:?}", head.method, uri, version);
+    if let Err(e) = write!(&mut stream, "{} {} {}{}", head.method, uri, version, LINE_ENDING) {
+        return Err((e, stream));
+    }
+
+    let body = match head.method {
+        Method::Get | Method::Head => None,
+        _ => {
+            let len = head.headers.get::<ContentLength>().map(|cl| **cl);
+            if len.is_none() {
+                // can't do in the match above, thanks borrowck
+                let encodings = match head.headers.get_mut::<TransferEncoding>() {
+                    Some(encodings) => {
+                        //TODO: check if chunked is already in encodings. use HashSet?
+                        encodings.push(Chunked);
+                        false
+                    },
+                    None => true
+                };
+                if encodings {
+                    head.headers.set(TransferEncoding(vec![Chunked]));
+                }
+            }
+            Some(len)
+        }
+    };
+
+    debug!("headers={:?}", head.headers);
+    if let Err(e) = write!(&mut stream, "{}{}", head.headers, LINE_ENDING) {
+        return Err((e, stream));
+    }
+    let writer = match body {
+        None => EmptyWriter(stream),
+        Some(None) => ChunkedWriter(stream),
+        Some(Some(len)) => SizedWriter(stream, len),
+    };
+    Ok((writer, head))
+}
+
+/// The shape of an `HttpWriter`, without its Writer.
+enum WriterKind {
+    Through,
+    Chunked,
+    Sized(u64),
+    Empty,
+}
+
+fn split_writer<W: Write>(writer: HttpWriter<W>) -> (W, WriterKind) {
+    match writer {
+        ThroughWriter(w) => (w, WriterKind::Through),
+        ChunkedWriter(w) => (w, WriterKind::Chunked),
+        SizedWriter(w, rem) => (w, WriterKind::Sized(rem)),
+        EmptyWriter(w) => (w, WriterKind::Empty),
+    }
+}
+
+fn join_writer<W: Write>(w: W, kind: WriterKind) -> HttpWriter<W> {
+    match kind {
+        WriterKind::Through => ThroughWriter(w),
+        WriterKind::Chunked => ChunkedWriter(w),
+        WriterKind::Sized(rem) => SizedWriter(w, rem),
+        WriterKind::Empty => EmptyWriter(w),
+    }
+}
+
+// Without socket timeouts, waiting for `100 Continue` could block forever on
+// servers that ignore `Expect`, so the body is always written right away.
+#[cfg(feature = "timeouts")]
+fn wait_continue_supported() -> bool {
+    true
+}
+
+#[cfg(not(feature = "timeouts"))]
+fn wait_continue_supported() -> bool {
+    false
+}
+
+#[cfg(feature = "timeouts")]
+fn set_continue_timeout(stream: &NetworkStream, timeout: Duration) -> io::Result<()> {
+    stream.set_read_timeout(Some(timeout))
+}
+
+#[cfg(not(feature = "timeouts"))]
+fn set_continue_timeout(_stream: &NetworkStream, _timeout: Duration) -> io::Result<()> {
+    Ok(())
+}
+
 /// The `Protocol` implementation provides HTTP/1.1 messages.
 pub struct Http11Protocol {
     connector: Connector,
@@ -483,6 +699,10 @@ pub enum HttpReader<R> {
     /// A Reader used when a Content-Length header is passed with a positive integer.
     SizedReader(R, u64),
     /// A Reader used when Transfer-Encoding is `chunked`.
+    ///
+    ///
+    /// The trailer section following the last chunk is left unread, unless
+    /// the reader is wrapped in a `TrailersReader`.
     ChunkedReader(R, Option<u64>),
     /// A Reader used for responses that don't indicate a length or chunked.
     ///
@@ -536,6 +756,184 @@ impl<R: Read> HttpReader<R> {
             EmptyReader(ref mut r) => r,
         }
     }
+
+    /// Reads the next whole chunk of a chunked body, with the extensions
+    /// from its chunk size line.
+    ///
+    /// This allows using chunk boundaries as message framing. Returns `None`
+    /// once the last chunk has been read, which the trailer section follows.
+    ///
+    /// Returns an `InvalidInput` error for any other kind of body, or if part
+    /// of the current chunk was already consumed with `read`. A chunk larger
+    /// than 8MB isn't buffered, but fails with `InvalidData`; its data can
+    /// still be read with `read`.
+    pub fn read_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        match *self {
+            ChunkedReader(ref mut body, ref mut opt_remaining) => {
+                match *opt_remaining {
+                    Some(0) => return Ok(None),
+                    Some(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                                         "Chunk partially read")),
+                    None => ()
+                }
+                let mut ext = Vec::new();
+                let size = try!(read_chunk_head(body, Some(&mut ext)));
+                trace!("read_chunk size={:?}, extensions={:?}", size, ext);
+                if size == 0 {
+                    *opt_remaining = Some(0);
+                    return Ok(None);
+                }
+                if size > MAX_CHUNK_SIZE {
+                    debug!("chunk of {} bytes is too large to read whole", size);
+                    *opt_remaining = Some(size);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Chunk too large"));
+                }
+
+                let mut data = Vec::new();
+                try!(body.by_ref().take(size).read_to_end(&mut data));
+                if (data.len() as u64) < size {
+                    *opt_remaining = Some(0);
+                    return Err(io::Error::new(io::ErrorKind::Other, "early eof"));
+                }
+                try!(eat(body, LINE_ENDING.as_bytes()));
+                Ok(Some(Chunk {
+                    extensions: parse_chunk_extensions(&ext),
+                    data: data,
+                }))
+            },
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "Body is not chunked"))
+        }
+    }
+
+    /// Reads and discards the rest of the body, if it is at most `limit` bytes.
+    ///
+    /// Returns whether the body was read to the end, so that the next message
+    /// can be read from the same connection. A body ending at EOF can never
+    /// be drained, and neither can a sized body with more than `limit` bytes
+    /// left. A chunked body is read until `limit` bytes are exceeded.
+    pub fn drain(&mut self, limit: u64) -> io::Result<bool> {
+        if let EmptyReader(..) = *self {
+            return Ok(true);
+        }
+        if !self.can_drain(limit) {
+            return Ok(false);
+        }
+        let drained = try!(io::copy(&mut self.by_ref().take(limit.saturating_add(1)),
+                                    &mut io::sink()));
+        trace!("drained {} bytes", drained);
+        Ok(match *self {
+            SizedReader(_, 0) | ChunkedReader(_, Some(0)) => true,
+            _ => false
+        })
+    }
+
+    /// Returns whether `drain(limit)` could read the rest of the body,
+    /// without reading any of it.
+    ///
+    /// A body without a length never can, and a chunked body can until a
+    /// chunk turns out to be too large.
+    pub fn can_drain(&self, limit: u64) -> bool {
+        match *self {
+            EmptyReader(..) => true,
+            EofReader(..) => false,
+            SizedReader(_, remaining) => remaining <= limit,
+            ChunkedReader(..) => true
+        }
+    }
+}
+
+/// An `HttpReader` that reads and keeps the trailers following a chunked
+/// body, once the last chunk has been read.
+pub struct TrailersReader<R> {
+    reader: HttpReader<R>,
+    trailers: Option<Headers>,
+}
+
+impl<R: Read> TrailersReader<R> {
+    /// Wraps an `HttpReader`.
+    pub fn new(reader: HttpReader<R>) -> TrailersReader<R> {
+        TrailersReader {
+            reader: reader,
+            trailers: None,
+        }
+    }
+
+    /// Unwraps this TrailersReader and returns the underlying HttpReader.
+    pub fn into_inner(self) -> HttpReader<R> {
+        self.reader
+    }
+
+    /// Gets a borrowed reference to the underlying HttpReader.
+    pub fn get_ref(&self) -> &HttpReader<R> {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying HttpReader.
+    pub fn get_mut(&mut self) -> &mut HttpReader<R> {
+        &mut self.reader
+    }
+
+    /// Reads the next whole chunk of a chunked body, and the trailers after
+    /// the last one.
+    ///
+    /// See `HttpReader::read_chunk`.
+    pub fn read_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        let chunk = try!(self.reader.read_chunk());
+        if chunk.is_none() {
+            try!(self.read_trailers());
+        }
+        Ok(chunk)
+    }
+
+    /// Reads and discards the rest of the body, and reads the trailers.
+    ///
+    /// See `HttpReader::drain`.
+    pub fn drain(&mut self, limit: u64) -> io::Result<bool> {
+        let drained = try!(self.reader.drain(limit));
+        if drained {
+            try!(self.read_trailers());
+        }
+        Ok(drained)
+    }
+
+    /// See `HttpReader::can_drain`.
+    #[inline]
+    pub fn can_drain(&self, limit: u64) -> bool {
+        self.reader.can_drain(limit)
+    }
+
+    /// Gets the trailers of a chunked body, once the body has been read to
+    /// the end.
+    ///
+    /// Returns `None` before then, and for any other kind of body.
+    pub fn trailers(&self) -> Option<&Headers> {
+        self.trailers.as_ref()
+    }
+
+    fn read_trailers(&mut self) -> io::Result<()> {
+        if self.trailers.is_none() {
+            if let ChunkedReader(ref mut body, Some(0)) = self.reader {
+                self.trailers = Some(try!(read_trailers(body)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for TrailersReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = try!(self.reader.read(buf));
+        if count == 0 && !buf.is_empty() {
+            try!(self.read_trailers());
+        }
+        Ok(count)
+    }
+}
+
+impl<R> fmt::Debug for TrailersReader<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "TrailersReader({:?}, trailers={:?})", self.reader, self.trailers)
+    }
 }
 
 impl<R> fmt::Debug for HttpReader<R> {
@@ -543,7 +941,8 @@ impl<R> fmt::Debug for HttpReader<R> {
         match *self {
             SizedReader(_,rem) => write!(fmt, "SizedReader(remaining={:?})", rem),
             ChunkedReader(_, None) => write!(fmt, "ChunkedReader(chunk_remaining=unknown)"),
-            ChunkedReader(_, Some(rem)) => write!(fmt, "ChunkedReader(chunk_remaining={:?})", rem),
+            ChunkedReader(_, Some(rem)) =>
+                write!(fmt, "ChunkedReader(chunk_remaining={:?})", rem),
             EofReader(_) => write!(fmt, "EofReader"),
             EmptyReader(_) => write!(fmt, "EmptyReader"),
         }
@@ -630,6 +1029,12 @@ fn eat<R: Read>(rdr: &mut R, bytes: &[u8]) -> io::Result<()> {
 
 /// Chunked chunks start with 1*HEXDIGIT, indicating the size of the chunk.
 fn read_chunk_size<R: Read>(rdr: &mut R) -> io::Result<u64> {
+    read_chunk_head(rdr, None)
+}
+
+/// Reads a chunk size line, pushing the raw bytes of any extensions, after
+/// the first ";", into `ext`.
+fn read_chunk_head<R: Read>(rdr: &mut R, mut ext: Option<&mut Vec<u8>>) -> io::Result<u64> {
     macro_rules! byte (
         ($rdr:ident) => ({
             let mut buf = [0];
@@ -678,12 +1083,14 @@ fn read_chunk_size<R: Read>(rdr: &mut R) -> io::Result<u64> {
             // LWS can follow the chunk size, but no more digits can come
             b'\t' | b' ' if in_chunk_size => in_chunk_size = false,
             // We allow any arbitrary octet once we are in the extension, since
-            // they all get ignored anyway. According to the HTTP spec, valid
-            // extensions would have a more strict syntax:
+            // they are only parsed leniently, if at all. According to the HTTP
+            // spec, valid extensions would have a more strict syntax:
             //     (token ["=" (token | quoted-string)])
             // but we gain nothing by rejecting an otherwise valid chunk size.
-            ext if in_ext => {
-                todo!("chunk extension byte={}", ext);
+            b if in_ext => {
+                if let Some(ref mut ext) = ext {
+                    ext.push(b);
+                }
             },
             // Finally, if we aren't in the extension and we're reading any
             // other octet, the chunk size line is invalid!
@@ -697,6 +1104,140 @@ fn read_chunk_size<R: Read>(rdr: &mut R) -> io::Result<u64> {
     Ok(size)
 }
 
+/// Parses the raw extensions of a chunk size line into names and values.
+///
+/// Quoted values are unquoted. Since extensions that don't follow the
+/// grammar are tolerated when reading, this never fails.
+fn parse_chunk_extensions(raw: &[u8]) -> Vec<(String, Option<String>)> {
+    let mut exts = Vec::new();
+    let mut parts = Vec::new();
+    let mut part = Vec::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for &b in raw {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if in_quotes => escaped = true,
+            b'"' => in_quotes = !in_quotes,
+            b';' if !in_quotes => {
+                parts.push(part);
+                part = Vec::new();
+                continue;
+            },
+            _ => ()
+        }
+        part.push(b);
+    }
+    parts.push(part);
+
+    for part in parts {
+        let part = String::from_utf8_lossy(&part).into_owned();
+        let mut split = part.splitn(2, '=');
+        let name = split.next().unwrap_or("").trim().to_owned();
+        if name.is_empty() {
+            continue;
+        }
+        let value = split.next().map(|value| {
+            let value = value.trim();
+            if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                unquote(&value[1..value.len() - 1])
+            } else {
+                value.to_owned()
+            }
+        });
+        exts.push((name, value));
+    }
+    exts
+}
+
+fn unquote(s: &str) -> String {
+    let mut unquoted = String::with_capacity(s.len());
+    let mut escaped = false;
+    for c in s.chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+        } else {
+            escaped = false;
+            unquoted.push(c);
+        }
+    }
+    unquoted
+}
+
+/// Writes the extensions of a chunk size line, quoting values that aren't
+/// tokens.
+fn write_chunk_extensions<W: Write>(w: &mut W, extensions: &[(&str, Option<&str>)])
+        -> io::Result<()> {
+    for &(name, value) in extensions {
+        if !is_token(name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "Invalid chunk extension name"));
+        }
+        try!(write!(w, ";{}", name));
+        match value {
+            Some(value) if is_token(value) => try!(write!(w, "={}", value)),
+            Some(value) => {
+                if value.bytes().any(|b| b == CR || b == LF) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "Invalid chunk extension value"));
+                }
+                try!(write!(w, "=\""));
+                for c in value.chars() {
+                    if c == '"' || c == '\\' {
+                        try!(write!(w, "\\"));
+                    }
+                    try!(write!(w, "{}", c));
+                }
+                try!(write!(w, "\""));
+            },
+            None => ()
+        }
+    }
+    Ok(())
+}
+
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| match b {
+        b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' |
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' |
+        b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => true,
+        _ => false
+    })
+}
+
+/// The trailer section follows the last chunk, and ends with an empty line.
+fn read_trailers<R: Read>(rdr: &mut R) -> io::Result<Headers> {
+    let mut raw = Vec::new();
+    let mut byte = [0];
+    loop {
+        match try!(rdr.read(&mut byte)) {
+            1 => raw.push(byte[0]),
+            // some servers close the connection right after the last chunk
+            _ if raw.is_empty() => return Ok(Headers::new()),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "Invalid trailers")),
+        }
+        if &raw[..] == LINE_ENDING.as_bytes() {
+            return Ok(Headers::new());
+        }
+        if raw.ends_with(b"\r\n\r\n") {
+            break;
+        }
+        if raw.len() > MAX_TRAILERS_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "Trailers too large"));
+        }
+    }
+    trace!("trailers={:?}", raw);
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    match httparse::parse_headers(&raw, &mut headers) {
+        Ok(httparse::Status::Complete((_, headers))) => Headers::from_raw(headers).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Invalid trailers")
+        }),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid trailers"))
+    }
+}
+
 fn should_have_response_body(method: &Method, status: u16) -> bool {
     trace!("should_have_response_body({:?}, {})", method, status);
     match (method, status) {
@@ -776,6 +1317,63 @@ impl<W: Write> HttpWriter<W> {
             Err(e) => Err(EndError(e, self))
         }
     }
+
+    /// Writes `data` as exactly one chunk, with `extensions` on its chunk
+    /// size line, instead of one chunk per `write`.
+    ///
+    /// The other variants write `data` as usual, and ignore the extensions.
+    /// Since an empty chunk would end the body, `data` can't be empty.
+    pub fn write_chunk(&mut self, data: &[u8], extensions: &[(&str, Option<&str>)])
+            -> io::Result<()> {
+        match *self {
+            ChunkedWriter(ref mut w) => {
+                if data.is_empty() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, "Empty chunk"));
+                }
+                trace!("chunked write_chunk, size = {:?}", data.len());
+                let mut head = Vec::new();
+                try!(write!(&mut head, "{:X}", data.len()));
+                try!(write_chunk_extensions(&mut head, extensions));
+                try!(w.write_all(&head));
+                try!(w.write_all(LINE_ENDING.as_bytes()));
+                try!(w.write_all(data));
+                w.write_all(LINE_ENDING.as_bytes())
+            },
+            _ => self.write_all(data)
+        }
+    }
+
+    /// Ends the HttpWriter like `end()`, with `trailers` following the
+    /// last-chunk of a ChunkedWriter.
+    ///
+    /// The other variants can't send trailers, and ignore them.
+    #[inline]
+    pub fn end_with_trailers(mut self, trailers: &Headers) -> Result<W, EndError<W>> {
+        match self.write_trailers(trailers) {
+            Ok(..) => Ok(self.into_inner()),
+            Err(e) => Err(EndError(e, self))
+        }
+    }
+
+    /// Writes the last-chunk of a ChunkedWriter, followed by `trailers`, and
+    /// flushes. The other variants just flush.
+    ///
+    /// Nothing else should be written afterwards.
+    pub fn write_trailers(&mut self, trailers: &Headers) -> io::Result<()> {
+        match *self {
+            ChunkedWriter(ref mut w) => {
+                trace!("chunked write, trailers = {:?}", trailers);
+                try!(write!(w, "0{}{}{}", LINE_ENDING, trailers, LINE_ENDING));
+            },
+            _ => {
+                if trailers.len() > 0 {
+                    debug!("Cannot send trailers without Transfer-Encoding: chunked");
+                }
+                try!(self.write(&[]));
+            }
+        }
+        self.flush()
+    }
 }
 
 #[derive(Debug)]
@@ -845,6 +1443,8 @@ impl<W: Write> fmt::Debug for HttpWriter<W> {
 }
 
 const MAX_HEADERS: usize = 100;
+const MAX_TRAILERS_SIZE: usize = 8192;
+const MAX_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
 
 /// Parses a request into an Incoming message head.
 #[inline]
@@ -974,7 +1574,7 @@ mod tests {
 
 
     use buffer::BufReader;
-    use mock::MockStream;
+    use testing::MockStream;
     use http::HttpMessage;
 
     use super::{read_chunk_size, parse_request, parse_response, Http11Message};
@@ -990,6 +1590,114 @@ mod tests {
         assert_eq!(s, "7\r\nfoo bar\r\nD\r\nbaz quux herp\r\n0\r\n\r\n");
     }
 
+    #[test]
+    fn test_write_chunked_trailers() {
+        use std::str::from_utf8;
+        use header::{Headers, ContentLength};
+        let mut trailers = Headers::new();
+        trailers.set_raw("Content-MD5", vec![b"abc".to_vec()]);
+
+        let mut w = super::HttpWriter::ChunkedWriter(Vec::new());
+        w.write_all(b"foo bar").unwrap();
+        let buf = w.end_with_trailers(&trailers).unwrap();
+        let s = from_utf8(buf.as_ref()).unwrap();
+        assert_eq!(s, "7\r\nfoo bar\r\n0\r\nContent-MD5: abc\r\n\r\n");
+
+        let mut w = super::HttpWriter::SizedWriter(Vec::new(), 3);
+        w.write_all(b"foo").unwrap();
+        trailers.set(ContentLength(3));
+        let buf = w.end_with_trailers(&trailers).unwrap();
+        assert_eq!(from_utf8(buf.as_ref()).unwrap(), "foo");
+    }
+
+    #[test]
+    fn test_read_chunked_trailers() {
+        let mut r = super::TrailersReader::new(super::HttpReader::ChunkedReader(
+            MockStream::with_input(b"\
+                3\r\n\
+                foo\r\n\
+                0\r\n\
+                Content-MD5: abc\r\n\
+                Server-Timing: db;dur=53\r\n\
+                \r\n\
+                HTTP/1.1"), None));
+
+        let mut s = String::new();
+        assert!(r.trailers().is_none());
+        r.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "foo");
+
+        let trailers = r.trailers().unwrap();
+        assert_eq!(trailers.get_raw("Content-MD5").unwrap(), &[b"abc".to_vec()][..]);
+        assert_eq!(trailers.get_raw("Server-Timing").unwrap(), &[b"db;dur=53".to_vec()][..]);
+
+        // the rest of the stream is left for the next message
+        let mut rest = String::new();
+        r.into_inner().into_inner().read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "HTTP/1.1");
+    }
+
+    #[test]
+    fn test_write_chunk_extensions() {
+        use std::str::from_utf8;
+        let mut w = super::HttpWriter::ChunkedWriter(Vec::new());
+        w.write_chunk(b"foo", &[]).unwrap();
+        w.write_chunk(b"bar", &[("id", Some("1")), ("last", None), ("note", Some("a \"b\""))])
+            .unwrap();
+        assert!(w.write_chunk(b"", &[]).is_err());
+        assert!(w.write_chunk(b"baz", &[("bad name", None)]).is_err());
+        let buf = w.end().unwrap();
+        let s = from_utf8(buf.as_ref()).unwrap();
+        assert_eq!(s, "3\r\nfoo\r\n3;id=1;last;note=\"a \\\"b\\\"\"\r\nbar\r\n0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_read_chunk_extensions() {
+        use http::Chunk;
+        let mut r = super::TrailersReader::new(super::HttpReader::ChunkedReader(
+            MockStream::with_input(b"\
+                3\r\n\
+                foo\r\n\
+                3 ; id=1;last; note=\"a \\\"b\\\"\"\r\n\
+                bar\r\n\
+                0\r\n\
+                \r\n"), None));
+
+        assert_eq!(r.read_chunk().unwrap(), Some(Chunk {
+            extensions: vec![],
+            data: b"foo".to_vec(),
+        }));
+        assert_eq!(r.read_chunk().unwrap(), Some(Chunk {
+            extensions: vec![
+                ("id".to_owned(), Some("1".to_owned())),
+                ("last".to_owned(), None),
+                ("note".to_owned(), Some("a \"b\"".to_owned())),
+            ],
+            data: b"bar".to_vec(),
+        }));
+        assert_eq!(r.read_chunk().unwrap(), None);
+        assert_eq!(r.read_chunk().unwrap(), None);
+        assert!(r.trailers().is_some());
+
+        let mut r = super::HttpReader::ChunkedReader(MockStream::with_input(b"\
+            3\r\n\
+            foo\r\n"), None);
+        let mut buf = [0u8; 1];
+        r.read(&mut buf).unwrap();
+        assert_eq!(r.read_chunk().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let mut r = super::HttpReader::ChunkedReader(MockStream::with_input(b"\
+            FFFFFFFFFFFF\r\n\
+            foo"), None);
+        assert_eq!(r.read_chunk().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let mut data = Vec::new();
+        assert!(r.read_to_end(&mut data).is_err());
+        assert_eq!(data, b"foo");
+
+        let mut r = super::HttpReader::SizedReader(MockStream::with_input(b"foo"), 3);
+        assert_eq!(r.read_chunk().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
     #[test]
     fn test_write_sized() {
         use std::str::from_utf8;
@@ -1068,6 +1776,25 @@ mod tests {
         assert_eq!(e.description(), "early eof");
     }
 
+    #[test]
+    fn test_drain() {
+        let mut r = super::HttpReader::SizedReader(MockStream::with_input(b"foo bar"), 7);
+        assert!(!r.can_drain(6));
+        assert!(r.can_drain(7));
+        assert!(!r.drain(6).unwrap());
+        assert!(r.drain(7).unwrap());
+
+        let body = b"3\r\nfoo\r\n4\r\n bar\r\n0\r\n\r\n";
+        let mut r = super::HttpReader::ChunkedReader(MockStream::with_input(body), None);
+        assert!(r.drain(7).unwrap());
+        let mut r = super::HttpReader::ChunkedReader(MockStream::with_input(body), None);
+        assert!(!r.drain(6).unwrap());
+
+        let mut r = super::HttpReader::EofReader(MockStream::with_input(b"foo"));
+        assert!(!r.can_drain(10));
+        assert!(!r.drain(10).unwrap());
+    }
+
     #[test]
     fn test_message_get_incoming_invalid_content_length() {
         let raw = MockStream::with_input(
@@ -1077,6 +1804,46 @@ mod tests {
         assert!(msg.close_connection().is_ok());
     }
 
+    #[cfg(feature = "timeouts")]
+    #[test]
+    fn test_wait_continue() {
+        use std::time::Duration;
+        use url::Url;
+        use header::{Headers, ContentLength, Expect};
+        use http::RequestHead;
+        use method::Method;
+
+        fn message(input: &[u8]) -> Http11Message {
+            let mut msg = Http11Message::with_stream(Box::new(MockStream::with_input(input)));
+            let mut headers = Headers::new();
+            headers.set(ContentLength(3));
+            headers.set(Expect::Continue);
+            msg.set_outgoing(RequestHead {
+                headers: headers,
+                method: Method::Post,
+                url: Url::parse("http://hyper.rs").unwrap(),
+            }).unwrap();
+            msg
+        }
+
+        let mut msg = message(b"HTTP/1.1 100 Continue\r\n\r\n");
+        assert!(msg.wait_continue(Duration::from_secs(1)).unwrap());
+        msg.write_all(b"foo").unwrap();
+        msg.flush_outgoing().unwrap();
+        let stream = msg.into_inner().downcast::<MockStream>().ok().unwrap();
+        assert!(stream.written().ends_with(b"\r\n\r\nfoo"));
+
+        let mut msg = message(b"HTTP/1.1 413 Payload Too Large\r\nContent-Length: 2\r\n\r\nno");
+        assert!(!msg.wait_continue(Duration::from_secs(1)).unwrap());
+        assert!(msg.write(b"foo").is_err());
+        let head = msg.get_incoming().unwrap();
+        assert_eq!(head.raw_status.0, 413);
+        let mut s = String::new();
+        msg.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "no");
+        assert!(msg.get_ref().downcast_ref::<MockStream>().unwrap().is_closed());
+    }
+
     #[test]
     fn test_parse_incoming() {
         let mut raw = MockStream::with_input(b"GET /echo HTTP/1.1\r\nHost: hyper.rs\r\n\r\n");
@@ -1119,11 +1886,10 @@ mod tests {
     #[cfg(feature = "nightly")]
     #[bench]
     fn bench_parse_incoming(b: &mut Bencher) {
-        let mut raw = MockStream::with_input(b"GET /echo HTTP/1.1\r\nHost: hyper.rs\r\n\r\n");
-        let mut buf = BufReader::new(&mut raw);
         b.iter(|| {
+            let mut raw = MockStream::with_input(b"GET /echo HTTP/1.1\r\nHost: hyper.rs\r\n\r\n");
+            let mut buf = BufReader::new(&mut raw);
             parse_request(&mut buf).unwrap();
-            buf.get_mut().read.set_position(0);
         });
     }
 }
@@ -435,7 +435,7 @@ mod tests {
 
     use std::io::{Read};
 
-    use mock::{MockHttp2Connector, MockStream};
+    use testing::{MockHttp2Connector, MockStream};
     use http::{RequestHead, ResponseHead, Protocol};
 
     use header::Headers;
@@ -600,11 +600,11 @@ mod tests {
         }).unwrap();
         let _ = message.get_incoming().unwrap();
 
-        let stream = stream.inner.lock().unwrap();
-        assert!(stream.write.len() > 0);
+        let written = stream.written();
+        assert!(written.len() > 0);
         // The output stream of the client side gets flipped so that we can read the stream from
         // the server's end.
-        let mut server_stream = MockStream::with_input(&stream.write);
+        let mut server_stream = MockStream::with_input(&written);
         assert_client_preface(&mut server_stream);
         let frame = server_stream.recv_frame().unwrap();
         assert!(match frame {
@@ -631,11 +631,11 @@ mod tests {
         message.write(&[2, 3]).unwrap();
         let _ = message.get_incoming().unwrap();
 
-        let stream = stream.inner.lock().unwrap();
-        assert!(stream.write.len() > 0);
+        let written = stream.written();
+        assert!(written.len() > 0);
         // The output stream of the client side gets flipped so that we can read the stream from
         // the server's end.
-        let mut server_stream = MockStream::with_input(&stream.write);
+        let mut server_stream = MockStream::with_input(&written);
         assert_client_preface(&mut server_stream);
         let frame = server_stream.recv_frame().unwrap();
         assert!(match frame {
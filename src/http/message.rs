@@ -3,21 +3,19 @@
 
 use std::any::{Any, TypeId};
 use std::fmt::Debug;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::mem;
-
-#[cfg(feature = "timeouts")]
-use std::io;
-#[cfg(feature = "timeouts")]
+use std::net::SocketAddr;
 use std::time::Duration;
 
 use typeable::Typeable;
 
 use header::Headers;
-use http::RawStatus;
+use http::{Chunk, RawStatus};
 use url::Url;
 
 use method;
+use net::{ConnectTiming, StreamCloser, TlsInfo};
 use version;
 use traitobject;
 
@@ -70,10 +68,94 @@ pub trait HttpMessage: Write + Read + Send + Any + Typeable + Debug {
     /// Set the write timeout duration for this message.
     #[cfg(feature = "timeouts")]
     fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()>;
+    /// Sets whether the outgoing request is sent to a proxy, which needs the absolute URL in
+    /// the request line, instead of only its path.
+    ///
+    /// By default, this is ignored.
+    fn set_proxied(&mut self, _proxied: bool) {}
+    /// Waits up to `timeout` for an interim `100 Continue` response, after a request head
+    /// including `Expect: 100-continue` has been set with `set_outgoing`.
+    ///
+    /// Returns `false` if the server answered with a final response instead, in which case the
+    /// body should not be written, and the response is returned by `get_incoming`.
+    ///
+    /// By default, this doesn't wait, and the body is always written.
+    fn wait_continue(&mut self, _timeout: Duration) -> ::Result<bool> {
+        Ok(true)
+    }
     /// Closes the underlying HTTP connection.
     fn close_connection(&mut self) -> ::Result<()>;
     /// Returns whether the incoming message has a body.
     fn has_body(&self) -> bool;
+    /// Reads and discards the rest of the incoming body, if it is at most `limit` bytes.
+    ///
+    /// Returns whether the body was read to the end, so that the connection can be reused.
+    /// By default, nothing is read, and only an already finished body counts as drained.
+    fn drain(&mut self, _limit: u64) -> io::Result<bool> {
+        Ok(!self.has_body())
+    }
+    /// Returns whether `drain(limit)` could read the rest of the incoming body, without
+    /// reading any of it.
+    ///
+    /// By default, only an already finished body can be drained.
+    fn can_drain(&self, _limit: u64) -> bool {
+        !self.has_body()
+    }
+    /// Gets the remote address of the underlying connection.
+    ///
+    /// By default, this isn't available, and an error is returned.
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Err(io::Error::new(io::ErrorKind::Other, "peer address not available"))
+    }
+    /// Returns whether the underlying connection was reused, such as from a `Pool`.
+    ///
+    /// By default, connections are never reused.
+    fn is_reused(&self) -> bool {
+        false
+    }
+    /// Gets the details of the TLS session protecting the underlying connection, if any.
+    ///
+    /// By default, there is none.
+    fn tls_info(&self) -> Option<TlsInfo> {
+        None
+    }
+    /// Gets how long each phase of opening the underlying connection took, if
+    /// they were measured.
+    ///
+    /// By default, they weren't.
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        None
+    }
+    /// Gets a handle that can shut down the underlying connection from another thread.
+    ///
+    /// By default, there is none.
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        None
+    }
+    /// Sets trailers to send after the body of the outgoing message.
+    ///
+    /// Trailers can only be sent with a chunked body. By default, they are ignored.
+    fn set_trailers(&mut self, _trailers: Headers) {}
+    /// Returns the trailers received after the body of the incoming message, once the body
+    /// has been read to the end.
+    ///
+    /// By default, there are never any trailers.
+    fn trailers(&self) -> Option<&Headers> {
+        None
+    }
+    /// Reads the next whole chunk of a chunked incoming body, with its extensions.
+    ///
+    /// By default, chunks can't be read, and an error is returned.
+    fn read_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "Body chunks not available"))
+    }
+    /// Writes `data` as exactly one chunk of a chunked outgoing body, with the given extensions.
+    ///
+    /// By default, chunks can't be written, and an error is returned.
+    fn write_chunk(&mut self, _data: &[u8], _extensions: &[(&str, Option<&str>)])
+            -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "Body chunks not available"))
+    }
 }
 
 impl HttpMessage {
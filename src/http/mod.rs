@@ -1,4 +1,5 @@
 //! Pieces pertaining to the HTTP message protocol.
+use std::ascii::AsciiExt;
 use std::borrow::Cow;
 
 use header::Connection;
@@ -16,6 +17,15 @@ pub mod h1;
 pub mod h2;
 pub mod message;
 
+/// A single chunk of a chunked body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    /// The extensions of the chunk, as names with optional values.
+    pub extensions: Vec<(String, Option<String>)>,
+    /// The data of the chunk.
+    pub data: Vec<u8>,
+}
+
 /// The raw status code and reason-phrase.
 #[derive(Clone, PartialEq, Debug)]
 pub struct RawStatus(pub u16, pub Cow<'static, str>);
@@ -47,6 +57,30 @@ pub fn should_keep_alive(version: HttpVersion, headers: &Headers) -> bool {
     }
 }
 
+/// Checks if the peer accepts trailers after a chunked body, with `TE: trailers`.
+pub fn accepts_trailers(headers: &Headers) -> bool {
+    match headers.get_raw("TE") {
+        Some(values) => values.iter().any(|value| {
+            value.split(|&b| b == b',').any(|coding| {
+                let coding = coding.split(|&b| b == b';').next().unwrap_or(coding);
+                let coding = String::from_utf8_lossy(coding);
+                coding.trim().eq_ignore_ascii_case("trailers")
+            })
+        }),
+        None => false
+    }
+}
+
+#[test]
+fn test_accepts_trailers() {
+    let mut headers = Headers::new();
+    assert!(!accepts_trailers(&headers));
+    headers.set_raw("TE", vec![b"gzip".to_vec()]);
+    assert!(!accepts_trailers(&headers));
+    headers.set_raw("TE", vec![b"gzip;q=0.5, Trailers".to_vec()]);
+    assert!(accepts_trailers(&headers));
+}
+
 #[test]
 fn test_should_keep_alive() {
     let mut headers = Headers::new();
@@ -138,6 +138,8 @@ extern crate serde;
 extern crate cookie;
 extern crate unicase;
 extern crate httparse;
+extern crate libc;
+extern crate net2;
 extern crate num_cpus;
 extern crate traitobject;
 extern crate typeable;
@@ -178,9 +180,9 @@ macro_rules! inspect(
     })
 );
 
-#[cfg(test)]
+#[cfg(any(test, feature = "testing"))]
 #[macro_use]
-mod mock;
+pub mod testing;
 #[doc(hidden)]
 pub mod buffer;
 pub mod client;
@@ -204,11 +206,14 @@ fn _assert_send<T: Send>() {
     _assert_send::<Client>();
     _assert_send::<client::Request<net::Fresh>>();
     _assert_send::<client::Response>();
+    _assert_send::<client::OwnedRequestBuilder>();
+    _assert_send::<client::AbortHandle>();
     _assert_send::<error::Error>();
 }
 
 #[allow(unconditional_recursion)]
 fn _assert_sync<T: Sync>() {
     _assert_sync::<Client>();
+    _assert_sync::<client::AbortHandle>();
     _assert_sync::<error::Error>();
 }
@@ -1,16 +1,28 @@
 //! A collection of traits abstracting over Listeners and Streams.
 use std::any::{Any, TypeId};
+use std::cmp;
 use std::fmt;
 use std::io::{self, ErrorKind, Read, Write};
 use std::net::{SocketAddr, ToSocketAddrs, TcpStream, TcpListener, Shutdown};
 use std::mem;
+use std::u32;
 
 #[cfg(feature = "openssl")]
 pub use self::openssl::Openssl;
 
-#[cfg(feature = "timeouts")]
+#[cfg(unix)]
+pub use self::unix::{UnixSocketListener, UnixSocketStream, UnixSocketConnector};
+
+pub use self::wire::{WireFormat, WireLog, WireLogConnector, WireLogListener, WireLogStream};
+
 use std::time::Duration;
 
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos",
+          target_os = "ios", target_os = "freebsd", target_os = "dragonfly",
+          target_os = "netbsd"))]
+use libc;
+use net2::{TcpBuilder, TcpStreamExt};
+use time::precise_time_ns;
 use typeable::Typeable;
 use traitobject;
 
@@ -66,6 +78,35 @@ pub trait NetworkStream: Read + Write + Any + Send + Typeable {
         Ok(())
     }
 
+    /// Get a handle that can shut down this stream from another thread,
+    /// waking up any blocked reads or writes.
+    ///
+    /// Returns `None` by default, if the stream doesn't support this.
+    #[inline]
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        None
+    }
+
+    /// Whether this stream is an existing connection that was reused, such
+    /// as an idle connection from a `Pool`.
+    #[inline]
+    fn is_reused(&self) -> bool {
+        false
+    }
+
+    /// Get the details of the TLS session protecting this stream, if any.
+    #[inline]
+    fn tls_info(&self) -> Option<TlsInfo> {
+        None
+    }
+
+    /// How long each phase of opening this stream's connection took, if
+    /// they were measured, such as by a `Pool`.
+    #[inline]
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        None
+    }
+
     // Unsure about name and implementation...
 
     #[doc(hidden)]
@@ -77,6 +118,23 @@ pub trait NetworkStream: Read + Write + Any + Send + Typeable {
     }
 }
 
+/// A handle to shut down a `NetworkStream` from another thread.
+pub trait StreamCloser: Send {
+    /// Shut down the stream.
+    fn close(&self, how: Shutdown) -> io::Result<()>;
+}
+
+impl StreamCloser for TcpStream {
+    #[inline]
+    fn close(&self, how: Shutdown) -> io::Result<()> {
+        match self.shutdown(how) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.kind() == ErrorKind::NotConnected => Ok(()),
+            err => err
+        }
+    }
+}
+
 /// A connector creates a NetworkStream.
 pub trait NetworkConnector {
     /// Type of Stream to create
@@ -84,6 +142,32 @@ pub trait NetworkConnector {
 
     /// Connect to a remote address.
     fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<Self::Stream>;
+
+    /// Connect to a remote address, and report how long each phase of
+    /// opening the connection took.
+    ///
+    /// By default, no phases are reported.
+    fn connect_timed(&self, host: &str, port: u16, scheme: &str)
+                     -> ::Result<(Self::Stream, ConnectTiming)> {
+        self.connect(host, port, scheme).map(|stream| (stream, ConnectTiming::default()))
+    }
+}
+
+/// A connector that can connect to a given address on behalf of a host,
+/// such as one picked by a load balancer.
+pub trait AddrConnector: NetworkConnector {
+    /// Connect to `addr`, using `host` for anything but the socket itself,
+    /// such as the server name of a TLS handshake.
+    fn connect_addr(&self, host: &str, addr: SocketAddr, scheme: &str) -> ::Result<Self::Stream>;
+
+    /// Connect to `addr` like `connect_addr`, and also return how long the
+    /// phases of opening the connection took.
+    ///
+    /// By default, no phases are reported.
+    fn connect_addr_timed(&self, host: &str, addr: SocketAddr, scheme: &str)
+                          -> ::Result<(Self::Stream, ConnectTiming)> {
+        self.connect_addr(host, addr, scheme).map(|stream| (stream, ConnectTiming::default()))
+    }
 }
 
 impl<T: NetworkStream + Send> From<T> for Box<NetworkStream + Send> {
@@ -208,26 +292,224 @@ impl NetworkStream + Send {
     }
 }
 
+/// Options applied to every TCP socket connected by a `ConfiguredConnector`,
+/// or accepted by an `HttpListener`.
+///
+/// Every option defaults to `None`, which leaves the operating system's
+/// setting untouched.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use hyper::Client;
+/// use hyper::net::{ConfiguredConnector, SocketOptions};
+///
+/// let mut options = SocketOptions::default();
+/// options.nodelay = Some(true);
+/// options.keepalive = Some(Duration::from_secs(60));
+/// let client = Client::with_connector(ConfiguredConnector::new(options));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SocketOptions {
+    /// Set `TCP_NODELAY`, disabling Nagle's algorithm when `true`.
+    pub nodelay: Option<bool>,
+    /// Enable `SO_KEEPALIVE`, sending probes after the connection has been
+    /// idle for this long.
+    pub keepalive: Option<Duration>,
+    /// The interval between keepalive probes, `TCP_KEEPINTVL`.
+    ///
+    /// The option counts whole seconds, so a fraction of a second is rounded
+    /// up. Only supported on Linux, Android, OS X, iOS, FreeBSD, DragonFly
+    /// and NetBSD; elsewhere it is ignored.
+    pub keepalive_interval: Option<Duration>,
+    /// How many unanswered keepalive probes drop the connection,
+    /// `TCP_KEEPCNT`.
+    ///
+    /// Supported on the same platforms as `keepalive_interval`.
+    pub keepalive_retries: Option<u32>,
+    /// Bind outgoing connections to this local address before connecting.
+    ///
+    /// Ignored by `HttpListener`, which is already bound.
+    pub local_addr: Option<SocketAddr>,
+    /// The size of the socket send buffer, `SO_SNDBUF`.
+    pub send_buffer_size: Option<usize>,
+    /// The size of the socket receive buffer, `SO_RCVBUF`.
+    pub recv_buffer_size: Option<usize>,
+}
+
+impl SocketOptions {
+    fn connect<To: ToSocketAddrs>(&self, addr: To, timing: &mut ConnectTiming)
+                                  -> io::Result<TcpStream> {
+        let start = precise_time_ns();
+        let addrs: Vec<SocketAddr> = try!(addr.to_socket_addrs()).collect();
+        let resolved = precise_time_ns();
+        timing.dns = Some(ns_to_duration(resolved - start));
+
+        let stream = match self.local_addr {
+            Some(local) => try!(connect_from(local, &addrs)),
+            None => try!(connect_any(&addrs)),
+        };
+        timing.connect = Some(ns_to_duration(precise_time_ns() - resolved));
+        try!(self.apply(&stream));
+        Ok(stream)
+    }
+
+    fn apply(&self, stream: &TcpStream) -> io::Result<()> {
+        if let Some(nodelay) = self.nodelay {
+            try!(stream.set_nodelay(nodelay));
+        }
+        if let Some(keepalive) = self.keepalive {
+            try!(TcpStreamExt::set_keepalive(stream, Some(keepalive)));
+        }
+        if let Some(interval) = self.keepalive_interval {
+            // whole seconds, and never 0, which the option refuses
+            let mut secs = interval.as_secs();
+            if interval.subsec_nanos() > 0 || secs == 0 {
+                secs += 1;
+            }
+            try!(set_keepalive_option(stream, KeepaliveOption::Interval,
+                                      cmp::min(secs, u32::MAX as u64) as u32));
+        }
+        if let Some(retries) = self.keepalive_retries {
+            try!(set_keepalive_option(stream, KeepaliveOption::Retries, retries));
+        }
+        if let Some(size) = self.send_buffer_size {
+            try!(stream.set_send_buffer_size(size));
+        }
+        if let Some(size) = self.recv_buffer_size {
+            try!(stream.set_recv_buffer_size(size));
+        }
+        Ok(())
+    }
+}
+
+enum KeepaliveOption {
+    Interval,
+    Retries,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos",
+          target_os = "ios", target_os = "freebsd", target_os = "dragonfly",
+          target_os = "netbsd"))]
+fn set_keepalive_option(stream: &TcpStream, option: KeepaliveOption, value: u32)
+                        -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let option = match option {
+        KeepaliveOption::Interval => libc::TCP_KEEPINTVL,
+        KeepaliveOption::Retries => libc::TCP_KEEPCNT,
+    };
+    let value = value as libc::c_int;
+    let ret = unsafe {
+        libc::setsockopt(stream.as_raw_fd(), libc::IPPROTO_TCP, option,
+                         &value as *const libc::c_int as *const libc::c_void,
+                         mem::size_of::<libc::c_int>() as libc::socklen_t)
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos",
+              target_os = "ios", target_os = "freebsd", target_os = "dragonfly",
+              target_os = "netbsd")))]
+fn set_keepalive_option(_stream: &TcpStream, _option: KeepaliveOption, _value: u32)
+                        -> io::Result<()> {
+    debug!("keepalive interval and retries are not supported on this platform, ignoring");
+    Ok(())
+}
+
+fn connect_any(addrs: &[SocketAddr]) -> io::Result<TcpStream> {
+    let mut last_err = None;
+    for &addr in addrs {
+        match TcpStream::connect(addr) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e)
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput,
+                       "could not resolve to any addresses")
+    }))
+}
+
+fn connect_from(local: SocketAddr, addrs: &[SocketAddr]) -> io::Result<TcpStream> {
+    let mut last_err = None;
+    for &addr in addrs {
+        let builder = match local {
+            SocketAddr::V4(..) if addr.is_ipv4() => try!(TcpBuilder::new_v4()),
+            SocketAddr::V6(..) if addr.is_ipv6() => try!(TcpBuilder::new_v6()),
+            // a local address of one family cannot reach a remote of the other
+            _ => continue
+        };
+        try!(builder.bind(local));
+        match builder.connect(addr) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e)
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput,
+                       "could not resolve to an address matching the local address")
+    }))
+}
+
+/// How long each phase of opening a connection took.
+///
+/// Phases that didn't happen, such as the TLS handshake of a plain
+/// connection, are `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConnectTiming {
+    /// Looking for an idle connection in a `Pool`. A connection reused from
+    /// a `Pool` has no other phases.
+    pub pool: Option<Duration>,
+    /// Resolving the host name to addresses.
+    pub dns: Option<Duration>,
+    /// Establishing the TCP connection.
+    pub connect: Option<Duration>,
+    /// The TLS handshake.
+    pub tls: Option<Duration>,
+}
+
+#[doc(hidden)]
+pub fn ns_to_duration(ns: u64) -> Duration {
+    Duration::new(ns / 1_000_000_000, (ns % 1_000_000_000) as u32)
+}
+
 /// A `NetworkListener` for `HttpStream`s.
-pub struct HttpListener(TcpListener);
+pub struct HttpListener(TcpListener, SocketOptions);
 
 impl Clone for HttpListener {
     #[inline]
     fn clone(&self) -> HttpListener {
-        HttpListener(self.0.try_clone().unwrap())
+        HttpListener(self.0.try_clone().unwrap(), self.1.clone())
     }
 }
 
 impl From<TcpListener> for HttpListener {
     fn from(listener: TcpListener) -> HttpListener {
-        HttpListener(listener)
+        HttpListener(listener, SocketOptions::default())
     }
 }
 
 impl HttpListener {
     /// Start listening to an address over HTTP.
     pub fn new<To: ToSocketAddrs>(addr: To) -> ::Result<HttpListener> {
-        Ok(HttpListener(try!(TcpListener::bind(addr))))
+        HttpListener::with_socket_options(addr, SocketOptions::default())
+    }
+
+    /// Start listening to an address over HTTP, applying `options` to every
+    /// accepted connection.
+    pub fn with_socket_options<To: ToSocketAddrs>(addr: To, options: SocketOptions)
+            -> ::Result<HttpListener> {
+        Ok(HttpListener(try!(TcpListener::bind(addr)), options))
+    }
+
+    /// Set the options applied to every accepted connection.
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        self.1 = options;
     }
 }
 
@@ -236,7 +518,9 @@ impl NetworkListener for HttpListener {
 
     #[inline]
     fn accept(&mut self) -> ::Result<HttpStream> {
-        Ok(HttpStream(try!(self.0.accept()).0))
+        let stream = try!(self.0.accept()).0;
+        try!(self.1.apply(&stream));
+        Ok(HttpStream(stream))
     }
 
     #[inline]
@@ -255,7 +539,7 @@ impl ::std::os::windows::io::AsRawSocket for HttpListener {
 #[cfg(windows)]
 impl ::std::os::windows::io::FromRawSocket for HttpListener {
     unsafe fn from_raw_socket(sock: ::std::os::windows::io::RawSocket) -> HttpListener {
-        HttpListener(TcpListener::from_raw_socket(sock))
+        HttpListener::from(TcpListener::from_raw_socket(sock))
     }
 }
 
@@ -269,7 +553,7 @@ impl ::std::os::unix::io::AsRawFd for HttpListener {
 #[cfg(unix)]
 impl ::std::os::unix::io::FromRawFd for HttpListener {
     unsafe fn from_raw_fd(fd: ::std::os::unix::io::RawFd) -> HttpListener {
-        HttpListener(TcpListener::from_raw_fd(fd))
+        HttpListener::from(TcpListener::from_raw_fd(fd))
     }
 }
 
@@ -362,6 +646,11 @@ impl NetworkStream for HttpStream {
             err => err
         }
     }
+
+    #[inline]
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        self.0.try_clone().ok().map(|s| Box::new(s) as Box<StreamCloser>)
+    }
 }
 
 /// A connector that will produce HttpStreams.
@@ -372,20 +661,92 @@ impl NetworkConnector for HttpConnector {
     type Stream = HttpStream;
 
     fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<HttpStream> {
-        let addr = &(host, port);
-        Ok(try!(match scheme {
-            "http" => {
-                debug!("http scheme");
-                Ok(HttpStream(try!(TcpStream::connect(addr))))
-            },
-            _ => {
-                Err(io::Error::new(io::ErrorKind::InvalidInput,
-                                "Invalid scheme for Http"))
-            }
-        }))
+        self.connect_timed(host, port, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_timed(&self, host: &str, port: u16, scheme: &str)
+                     -> ::Result<(HttpStream, ConnectTiming)> {
+        let mut timing = ConnectTiming::default();
+        let stream = try!(connect_http(&SocketOptions::default(), &(host, port), scheme,
+                                       &mut timing));
+        Ok((stream, timing))
+    }
+}
+
+impl AddrConnector for HttpConnector {
+    fn connect_addr(&self, host: &str, addr: SocketAddr, scheme: &str) -> ::Result<HttpStream> {
+        self.connect_addr_timed(host, addr, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_addr_timed(&self, _host: &str, addr: SocketAddr, scheme: &str)
+                          -> ::Result<(HttpStream, ConnectTiming)> {
+        let mut timing = ConnectTiming::default();
+        let stream = try!(connect_http(&SocketOptions::default(), addr, scheme, &mut timing));
+        Ok((stream, timing))
     }
 }
 
+/// A connector that will produce HttpStreams, applying `SocketOptions` to
+/// every connected socket.
+#[derive(Debug, Clone, Default)]
+pub struct ConfiguredConnector {
+    options: SocketOptions,
+}
+
+impl ConfiguredConnector {
+    /// Create a connector that applies `options` to every connected socket.
+    pub fn new(options: SocketOptions) -> ConfiguredConnector {
+        ConfiguredConnector { options: options }
+    }
+
+    /// Set the options applied to every connected socket.
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        self.options = options;
+    }
+}
+
+impl NetworkConnector for ConfiguredConnector {
+    type Stream = HttpStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<HttpStream> {
+        self.connect_timed(host, port, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_timed(&self, host: &str, port: u16, scheme: &str)
+                     -> ::Result<(HttpStream, ConnectTiming)> {
+        let mut timing = ConnectTiming::default();
+        let stream = try!(connect_http(&self.options, &(host, port), scheme, &mut timing));
+        Ok((stream, timing))
+    }
+}
+
+impl AddrConnector for ConfiguredConnector {
+    fn connect_addr(&self, host: &str, addr: SocketAddr, scheme: &str) -> ::Result<HttpStream> {
+        self.connect_addr_timed(host, addr, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_addr_timed(&self, _host: &str, addr: SocketAddr, scheme: &str)
+                          -> ::Result<(HttpStream, ConnectTiming)> {
+        let mut timing = ConnectTiming::default();
+        let stream = try!(connect_http(&self.options, addr, scheme, &mut timing));
+        Ok((stream, timing))
+    }
+}
+
+fn connect_http<A: ToSocketAddrs>(options: &SocketOptions, addr: A, scheme: &str,
+                                  timing: &mut ConnectTiming) -> ::Result<HttpStream> {
+    Ok(try!(match scheme {
+        "http" => {
+            debug!("http scheme");
+            Ok(HttpStream(try!(options.connect(addr, timing))))
+        },
+        _ => {
+            Err(io::Error::new(io::ErrorKind::InvalidInput,
+                            "Invalid scheme for Http"))
+        }
+    }))
+}
+
 /// A closure as a connector used to generate TcpStreams per request
 ///
 /// # Example
@@ -425,6 +786,21 @@ pub trait Ssl {
     fn wrap_server(&self, stream: HttpStream) -> ::Result<Self::Stream>;
 }
 
+/// The details of a negotiated TLS session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsInfo {
+    /// The protocol version, such as `TLSv1.2`.
+    pub version: String,
+    /// The name of the cipher suite.
+    pub cipher: String,
+    /// The DER encoded certificate chain of the peer, starting with its own
+    /// certificate. Empty if the peer didn't present one.
+    ///
+    /// **NOTE**: openssl 0.6 doesn't expose the rest of the chain, so the
+    /// `Openssl` streams only return the peer's own certificate here.
+    pub peer_certificates: Vec<Vec<u8>>,
+}
+
 /// A stream over the HTTP protocol, possibly protected by SSL.
 #[derive(Debug, Clone)]
 pub enum HttpsStream<S: NetworkStream> {
@@ -496,6 +872,22 @@ impl<S: NetworkStream> NetworkStream for HttpsStream<S> {
             HttpsStream::Https(ref mut s) => s.close(how)
         }
     }
+
+    #[inline]
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        match *self {
+            HttpsStream::Http(ref s) => s.closer(),
+            HttpsStream::Https(ref s) => s.closer()
+        }
+    }
+
+    #[inline]
+    fn tls_info(&self) -> Option<TlsInfo> {
+        match *self {
+            HttpsStream::Http(_) => None,
+            HttpsStream::Https(ref s) => s.tls_info()
+        }
+    }
 }
 
 /// A Http Listener over SSL.
@@ -540,31 +932,69 @@ impl<S: Ssl + Clone> NetworkListener for HttpsListener<S> {
 /// A connector that can protect HTTP streams using SSL.
 #[derive(Debug, Default)]
 pub struct HttpsConnector<S: Ssl> {
-    ssl: S
+    ssl: S,
+    http: ConfiguredConnector,
 }
 
 impl<S: Ssl> HttpsConnector<S> {
     /// Create a new connector using the provided SSL implementation.
     pub fn new(s: S) -> HttpsConnector<S> {
-        HttpsConnector { ssl: s }
+        HttpsConnector { ssl: s, http: ConfiguredConnector::default() }
     }
-}
 
-impl<S: Ssl> NetworkConnector for HttpsConnector<S> {
-    type Stream = HttpsStream<S::Stream>;
+    /// Set the options applied to every connected socket, before any SSL
+    /// handshake.
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        self.http.set_socket_options(options);
+    }
+}
 
-    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<Self::Stream> {
-        let addr = &(host, port);
+impl<S: Ssl> HttpsConnector<S> {
+    fn connect_to<A: ToSocketAddrs>(&self, host: &str, addr: A, scheme: &str,
+                                    timing: &mut ConnectTiming)
+                                    -> ::Result<HttpsStream<S::Stream>> {
         if scheme == "https" {
             debug!("https scheme");
-            let stream = HttpStream(try!(TcpStream::connect(addr)));
-            self.ssl.wrap_client(stream, host).map(HttpsStream::Https)
+            let stream = HttpStream(try!(self.http.options.connect(addr, timing)));
+            let start = precise_time_ns();
+            let stream = try!(self.ssl.wrap_client(stream, host));
+            timing.tls = Some(ns_to_duration(precise_time_ns() - start));
+            Ok(HttpsStream::Https(stream))
         } else {
-            HttpConnector.connect(host, port, scheme).map(HttpsStream::Http)
+            connect_http(&self.http.options, addr, scheme, timing).map(HttpsStream::Http)
         }
     }
 }
 
+impl<S: Ssl> NetworkConnector for HttpsConnector<S> {
+    type Stream = HttpsStream<S::Stream>;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<Self::Stream> {
+        self.connect_timed(host, port, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_timed(&self, host: &str, port: u16, scheme: &str)
+                     -> ::Result<(Self::Stream, ConnectTiming)> {
+        let mut timing = ConnectTiming::default();
+        let stream = try!(self.connect_to(host, &(host, port), scheme, &mut timing));
+        Ok((stream, timing))
+    }
+}
+
+impl<S: Ssl> AddrConnector for HttpsConnector<S> {
+    fn connect_addr(&self, host: &str, addr: SocketAddr, scheme: &str)
+                    -> ::Result<Self::Stream> {
+        self.connect_addr_timed(host, addr, scheme).map(|(stream, _)| stream)
+    }
+
+    fn connect_addr_timed(&self, host: &str, addr: SocketAddr, scheme: &str)
+                          -> ::Result<(Self::Stream, ConnectTiming)> {
+        let mut timing = ConnectTiming::default();
+        let stream = try!(self.connect_to(host, addr, scheme, &mut timing));
+        Ok((stream, timing))
+    }
+}
+
 
 #[cfg(not(feature = "openssl"))]
 #[doc(hidden)]
@@ -587,7 +1017,7 @@ mod openssl {
     use openssl::ssl::error::StreamError as SslIoError;
     use openssl::ssl::error::SslError;
     use openssl::x509::X509FileType;
-    use super::{NetworkStream, HttpStream};
+    use super::{NetworkStream, HttpStream, StreamCloser, TlsInfo};
 
     /// An implementation of `Ssl` for OpenSSL.
     ///
@@ -675,12 +1105,651 @@ mod openssl {
         fn close(&mut self, how: Shutdown) -> io::Result<()> {
             self.get_mut().close(how)
         }
+
+        fn closer(&self) -> Option<Box<StreamCloser>> {
+            self.get_ref().closer()
+        }
+
+        fn tls_info(&self) -> Option<TlsInfo> {
+            let ssl = self.ssl();
+            let cipher = match ssl.get_current_cipher() {
+                Some(cipher) => cipher.name().to_owned(),
+                // the handshake hasn't completed
+                None => return None
+            };
+            Some(TlsInfo {
+                version: ssl.version().to_owned(),
+                cipher: cipher,
+                peer_certificates: ssl.get_peer_certificate()
+                    .and_then(|cert| cert.save_der().ok())
+                    .into_iter()
+                    .collect(),
+            })
+        }
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::io::{self, ErrorKind, Read, Write};
+    use std::net::{SocketAddr, SocketAddrV4, Ipv4Addr, Shutdown};
+    use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+    use std::os::unix::net::{UnixStream, UnixListener};
+    use std::path::{Path, PathBuf};
+    #[cfg(feature = "timeouts")]
+    use std::time::Duration;
+
+    use super::{NetworkConnector, NetworkListener, NetworkStream, StreamCloser};
+
+    /// Unix domain sockets have no IP address, but `NetworkStream` and
+    /// `NetworkListener` must report a `SocketAddr`, so the unspecified
+    /// address `0.0.0.0:0` is used instead.
+    fn unix_socket_addr() -> SocketAddr {
+        SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 0))
+    }
+
+    /// A `NetworkListener` for `UnixSocketStream`s.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hyper::Server;
+    /// use hyper::net::UnixSocketListener;
+    ///
+    /// let listener = UnixSocketListener::new("/tmp/hyper.sock").unwrap();
+    /// Server::new(listener);
+    /// ```
+    pub struct UnixSocketListener(UnixListener);
+
+    impl Clone for UnixSocketListener {
+        #[inline]
+        fn clone(&self) -> UnixSocketListener {
+            UnixSocketListener(self.0.try_clone().unwrap())
+        }
+    }
+
+    impl From<UnixListener> for UnixSocketListener {
+        fn from(listener: UnixListener) -> UnixSocketListener {
+            UnixSocketListener(listener)
+        }
+    }
+
+    impl fmt::Debug for UnixSocketListener {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("UnixSocketListener(_)")
+        }
+    }
+
+    impl UnixSocketListener {
+        /// Start listening on a Unix domain socket at the given path.
+        pub fn new<P: AsRef<Path>>(path: P) -> ::Result<UnixSocketListener> {
+            Ok(UnixSocketListener(try!(UnixListener::bind(path))))
+        }
+    }
+
+    impl NetworkListener for UnixSocketListener {
+        type Stream = UnixSocketStream;
+
+        #[inline]
+        fn accept(&mut self) -> ::Result<UnixSocketStream> {
+            Ok(UnixSocketStream(try!(self.0.accept()).0))
+        }
+
+        /// Always `0.0.0.0:0`, since Unix sockets have no IP address.
+        #[inline]
+        fn local_addr(&mut self) -> io::Result<SocketAddr> {
+            Ok(unix_socket_addr())
+        }
+    }
+
+    impl AsRawFd for UnixSocketListener {
+        fn as_raw_fd(&self) -> RawFd {
+            self.0.as_raw_fd()
+        }
+    }
+
+    impl FromRawFd for UnixSocketListener {
+        unsafe fn from_raw_fd(fd: RawFd) -> UnixSocketListener {
+            UnixSocketListener(UnixListener::from_raw_fd(fd))
+        }
+    }
+
+    /// A wrapper around a `UnixStream`.
+    pub struct UnixSocketStream(pub UnixStream);
+
+    impl Clone for UnixSocketStream {
+        #[inline]
+        fn clone(&self) -> UnixSocketStream {
+            UnixSocketStream(self.0.try_clone().unwrap())
+        }
+    }
+
+    impl fmt::Debug for UnixSocketStream {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("UnixSocketStream(_)")
+        }
+    }
+
+    impl Read for UnixSocketStream {
+        #[inline]
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Write for UnixSocketStream {
+        #[inline]
+        fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+            self.0.write(msg)
+        }
+        #[inline]
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.flush()
+        }
+    }
+
+    impl AsRawFd for UnixSocketStream {
+        fn as_raw_fd(&self) -> RawFd {
+            self.0.as_raw_fd()
+        }
+    }
+
+    impl FromRawFd for UnixSocketStream {
+        unsafe fn from_raw_fd(fd: RawFd) -> UnixSocketStream {
+            UnixSocketStream(UnixStream::from_raw_fd(fd))
+        }
+    }
+
+    impl NetworkStream for UnixSocketStream {
+        /// Always `0.0.0.0:0`, since Unix sockets have no IP address.
+        #[inline]
+        fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+            Ok(unix_socket_addr())
+        }
+
+        #[cfg(feature = "timeouts")]
+        #[inline]
+        fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+            self.0.set_read_timeout(dur)
+        }
+
+        #[cfg(feature = "timeouts")]
+        #[inline]
+        fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+            self.0.set_write_timeout(dur)
+        }
+
+        #[inline]
+        fn close(&mut self, how: Shutdown) -> io::Result<()> {
+            match self.0.shutdown(how) {
+                Ok(_) => Ok(()),
+                Err(ref e) if e.kind() == ErrorKind::NotConnected => Ok(()),
+                err => err
+            }
+        }
+
+        #[inline]
+        fn closer(&self) -> Option<Box<StreamCloser>> {
+            self.0.try_clone().ok().map(|s| Box::new(s) as Box<StreamCloser>)
+        }
+    }
+
+    impl StreamCloser for UnixStream {
+        #[inline]
+        fn close(&self, how: Shutdown) -> io::Result<()> {
+            match self.shutdown(how) {
+                Ok(_) => Ok(()),
+                Err(ref e) if e.kind() == ErrorKind::NotConnected => Ok(()),
+                err => err
+            }
+        }
+    }
+
+    /// A connector that will produce `UnixSocketStream`s.
+    ///
+    /// The host of a request URL picks the socket path: hosts added with
+    /// `add_host` use their own path, and any other host uses the path given
+    /// to `new`. The port is ignored, and only the `http` scheme is accepted.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hyper::Client;
+    /// use hyper::net::UnixSocketConnector;
+    ///
+    /// let client = Client::with_connector(UnixSocketConnector::new("/var/run/docker.sock"));
+    /// client.get("http://docker/containers/json").send().unwrap();
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct UnixSocketConnector {
+        default: Option<PathBuf>,
+        hosts: HashMap<String, PathBuf>,
+    }
+
+    impl UnixSocketConnector {
+        /// Create a connector that connects every host to the socket at `path`.
+        pub fn new<P: AsRef<Path>>(path: P) -> UnixSocketConnector {
+            UnixSocketConnector {
+                default: Some(path.as_ref().to_path_buf()),
+                hosts: HashMap::new(),
+            }
+        }
+
+        /// Connect requests for `host` to the socket at `path`.
+        pub fn add_host<H, P>(&mut self, host: H, path: P)
+        where H: Into<String>, P: AsRef<Path> {
+            self.hosts.insert(host.into(), path.as_ref().to_path_buf());
+        }
+    }
+
+    impl NetworkConnector for UnixSocketConnector {
+        type Stream = UnixSocketStream;
+
+        fn connect(&self, host: &str, _port: u16, scheme: &str) -> ::Result<UnixSocketStream> {
+            if scheme != "http" {
+                return Err(From::from(io::Error::new(io::ErrorKind::InvalidInput,
+                                                     "Invalid scheme for Unix socket")));
+            }
+            match self.hosts.get(host).or(self.default.as_ref()) {
+                Some(path) => {
+                    debug!("unix socket {:?} for host {:?}", path, host);
+                    Ok(UnixSocketStream(try!(UnixStream::connect(path))))
+                },
+                None => Err(From::from(io::Error::new(io::ErrorKind::InvalidInput,
+                                                      "No Unix socket for host")))
+            }
+        }
+    }
+}
+
+mod wire {
+    use std::ascii::AsciiExt;
+    use std::fmt;
+    use std::io::{self, Read, Write};
+    use std::net::{SocketAddr, Shutdown};
+    use std::str;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    #[cfg(feature = "timeouts")]
+    use std::time::Duration;
+
+    use time;
+
+    use super::{ConnectTiming, NetworkConnector, NetworkListener, NetworkStream, StreamCloser,
+                TlsInfo};
+
+    /// How a `WireLog` writes the bytes of a stream.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum WireFormat {
+        /// 16 bytes per line, in hex and as ASCII.
+        Hexdump,
+        /// Line by line, with anything but printable ASCII escaped.
+        Text,
+    }
+
+    /// A thread-safe sink for the traffic of `WireLogStream`s.
+    ///
+    /// Every chunk read or written is logged with a timestamp, the ID of its
+    /// connection, and its direction: `>` for written, `<` for read. Clones
+    /// of a `WireLog` share the same sink and connection IDs.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::io;
+    /// use hyper::Client;
+    /// use hyper::net::{HttpConnector, WireFormat, WireLog, WireLogConnector};
+    ///
+    /// let mut log = WireLog::new(io::stderr(), WireFormat::Text);
+    /// log.set_redact(true);
+    /// let connector = WireLogConnector::new(HttpConnector::default(), log);
+    /// let client = Client::with_connector(connector);
+    /// ```
+    ///
+    /// As the log wraps the streams of a connector or listener, it sees what
+    /// hyper reads and writes, after TLS decryption.
+    #[derive(Clone)]
+    pub struct WireLog {
+        sink: Arc<Mutex<Box<Write + Send>>>,
+        format: WireFormat,
+        redact: bool,
+        next_id: Arc<AtomicUsize>,
+    }
+
+    impl fmt::Debug for WireLog {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_struct("WireLog")
+                .field("format", &self.format)
+                .field("redact", &self.redact)
+                .finish()
+        }
+    }
+
+    const REDACTED_HEADERS: [&'static str; 4] = [
+        "Authorization",
+        "Proxy-Authorization",
+        "Cookie",
+        "Set-Cookie",
+    ];
+
+    impl WireLog {
+        /// Create a log writing to `sink` in `format`.
+        pub fn new<W: Write + Send + 'static>(sink: W, format: WireFormat) -> WireLog {
+            WireLog {
+                sink: Arc::new(Mutex::new(Box::new(sink))),
+                format: format,
+                redact: false,
+                next_id: Arc::new(AtomicUsize::new(1)),
+            }
+        }
+
+        /// Set whether the values of `Authorization`, `Proxy-Authorization`,
+        /// `Cookie` and `Set-Cookie` headers are left out of the log.
+        ///
+        /// Headers are found line by line in each chunk, up to its first empty
+        /// line, so a header line split across two reads or writes may not be
+        /// redacted. The logged byte counts are those of the real traffic.
+        pub fn set_redact(&mut self, redact: bool) {
+            self.redact = redact;
+        }
+
+        /// Wrap `stream` to log its traffic, under a new connection ID.
+        ///
+        /// `what` describes the connection in the log, such as where it's to.
+        pub fn wrap<S: NetworkStream>(&self, stream: S, what: &str) -> WireLogStream<S> {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            self.event(id, what);
+            WireLogStream {
+                inner: stream,
+                id: id,
+                log: self.clone(),
+            }
+        }
+
+        fn event(&self, id: usize, what: &str) {
+            let mut sink = self.sink.lock().unwrap();
+            let _ = writeln!(sink, "[{} #{}] {}", timestamp(), id, what);
+        }
+
+        fn data(&self, id: usize, direction: char, bytes: &[u8]) {
+            if bytes.is_empty() {
+                return;
+            }
+            let len = bytes.len();
+            let redacted;
+            let bytes = if self.redact {
+                redacted = redact(bytes);
+                &redacted[..]
+            } else {
+                bytes
+            };
+
+            let mut sink = self.sink.lock().unwrap();
+            let _ = writeln!(sink, "[{} #{} {}] {} bytes", timestamp(), id, direction, len);
+            let _ = match self.format {
+                WireFormat::Hexdump => hexdump(&mut *sink, bytes),
+                WireFormat::Text => text(&mut *sink, bytes),
+            };
+        }
+    }
+
+    fn timestamp() -> String {
+        let now = time::now_utc();
+        let secs = now.strftime("%Y-%m-%dT%H:%M:%S").map(|t| t.to_string()).unwrap_or(String::new());
+        format!("{}.{:03}Z", secs, now.tm_nsec / 1_000_000)
+    }
+
+    fn hexdump(w: &mut Write, bytes: &[u8]) -> io::Result<()> {
+        for (i, line) in bytes.chunks(16).enumerate() {
+            try!(write!(w, "    {:08x} ", i * 16));
+            for j in 0..16 {
+                match line.get(j) {
+                    Some(b) => try!(write!(w, " {:02x}", b)),
+                    None => try!(write!(w, "   "))
+                }
+            }
+            try!(write!(w, "  |"));
+            for &b in line {
+                let c = if b >= 0x20 && b < 0x7f { b as char } else { '.' };
+                try!(write!(w, "{}", c));
+            }
+            try!(writeln!(w, "|"));
+        }
+        Ok(())
+    }
+
+    fn text(w: &mut Write, bytes: &[u8]) -> io::Result<()> {
+        for line in lines(bytes) {
+            try!(write!(w, "    "));
+            for &b in line {
+                match b {
+                    b'\r' => try!(write!(w, "\\r")),
+                    b'\n' => try!(write!(w, "\\n")),
+                    b'\\' => try!(write!(w, "\\\\")),
+                    0x20...0x7e => try!(write!(w, "{}", b as char)),
+                    _ => try!(write!(w, "\\x{:02x}", b))
+                }
+            }
+            try!(writeln!(w, ""));
+        }
+        Ok(())
+    }
+
+    // Splits after each `\n`, keeping it.
+    fn lines(bytes: &[u8]) -> Vec<&[u8]> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while start < bytes.len() {
+            let end = bytes[start..].iter().position(|&b| b == b'\n')
+                .map_or(bytes.len(), |i| start + i + 1);
+            lines.push(&bytes[start..end]);
+            start = end;
+        }
+        lines
+    }
+
+    // Redacts header lines, up to the first empty line. What follows it is
+    // a body, and is kept as it is.
+    fn redact(bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut in_head = true;
+        for line in lines(bytes) {
+            if !in_head {
+                out.extend(line.iter().cloned());
+                continue;
+            }
+            if line == b"\r\n" || line == b"\n" {
+                in_head = false;
+            }
+            let name = line.iter().position(|&b| b == b':')
+                .and_then(|colon| str::from_utf8(&line[..colon]).ok());
+            match name {
+                Some(name) if REDACTED_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name)) => {
+                    out.extend(name.bytes());
+                    out.extend(b": [redacted]".iter().cloned());
+                    let eol = if line.ends_with(b"\r\n") {
+                        &b"\r\n"[..]
+                    } else if line.ends_with(b"\n") {
+                        &b"\n"[..]
+                    } else {
+                        &b""[..]
+                    };
+                    out.extend(eol.iter().cloned());
+                },
+                _ => out.extend(line.iter().cloned())
+            }
+        }
+        out
+    }
+
+    /// A stream logging its traffic to a `WireLog`.
+    pub struct WireLogStream<S> {
+        inner: S,
+        id: usize,
+        log: WireLog,
+    }
+
+    impl<S> WireLogStream<S> {
+        /// The connection ID of this stream in the log.
+        pub fn id(&self) -> usize {
+            self.id
+        }
+
+        /// Get a reference to the wrapped stream.
+        pub fn get_ref(&self) -> &S {
+            &self.inner
+        }
+    }
+
+    impl<S: Clone> Clone for WireLogStream<S> {
+        fn clone(&self) -> WireLogStream<S> {
+            WireLogStream {
+                inner: self.inner.clone(),
+                id: self.id,
+                log: self.log.clone(),
+            }
+        }
+    }
+
+    impl<S: Read> Read for WireLogStream<S> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.inner.read(buf) {
+                Ok(n) => {
+                    self.log.data(self.id, '<', &buf[..n]);
+                    Ok(n)
+                },
+                Err(e) => {
+                    self.log.event(self.id, &format!("read error: {}", e));
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    impl<S: Write> Write for WireLogStream<S> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            match self.inner.write(buf) {
+                Ok(n) => {
+                    self.log.data(self.id, '>', &buf[..n]);
+                    Ok(n)
+                },
+                Err(e) => {
+                    self.log.event(self.id, &format!("write error: {}", e));
+                    Err(e)
+                }
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl<S: NetworkStream> NetworkStream for WireLogStream<S> {
+        fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+            self.inner.peer_addr()
+        }
+
+        #[cfg(feature = "timeouts")]
+        fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+            self.inner.set_read_timeout(dur)
+        }
+
+        #[cfg(feature = "timeouts")]
+        fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+            self.inner.set_write_timeout(dur)
+        }
+
+        fn close(&mut self, how: Shutdown) -> io::Result<()> {
+            self.log.event(self.id, &format!("close {:?}", how));
+            self.inner.close(how)
+        }
+
+        fn closer(&self) -> Option<Box<StreamCloser>> {
+            self.inner.closer()
+        }
+
+        fn is_reused(&self) -> bool {
+            self.inner.is_reused()
+        }
+
+        fn tls_info(&self) -> Option<TlsInfo> {
+            self.inner.tls_info()
+        }
+
+        fn connect_timing(&self) -> Option<ConnectTiming> {
+            self.inner.connect_timing()
+        }
+    }
+
+    /// A `NetworkConnector` logging the traffic of its connections to a
+    /// `WireLog`.
+    pub struct WireLogConnector<C> {
+        connector: C,
+        log: WireLog,
+    }
+
+    impl<C: NetworkConnector> WireLogConnector<C> {
+        /// Wrap `connector` to log to `log`.
+        pub fn new(connector: C, log: WireLog) -> WireLogConnector<C> {
+            WireLogConnector {
+                connector: connector,
+                log: log,
+            }
+        }
+    }
+
+    impl<C> NetworkConnector for WireLogConnector<C>
+    where C: NetworkConnector, C::Stream: NetworkStream + Send {
+        type Stream = WireLogStream<C::Stream>;
+
+        fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<Self::Stream> {
+            let stream = try!(self.connector.connect(host, port, scheme));
+            Ok(self.log.wrap(stream, &format!("connected to {}://{}:{}", scheme, host, port)))
+        }
+    }
+
+    /// A `NetworkListener` logging the traffic of its connections to a
+    /// `WireLog`.
+    #[derive(Clone)]
+    pub struct WireLogListener<L> {
+        listener: L,
+        log: WireLog,
+    }
+
+    impl<L: NetworkListener> WireLogListener<L> {
+        /// Wrap `listener` to log to `log`.
+        pub fn new(listener: L, log: WireLog) -> WireLogListener<L> {
+            WireLogListener {
+                listener: listener,
+                log: log,
+            }
+        }
+    }
+
+    impl<L: NetworkListener> NetworkListener for WireLogListener<L> {
+        type Stream = WireLogStream<L::Stream>;
+
+        fn accept(&mut self) -> ::Result<Self::Stream> {
+            let mut stream = try!(self.listener.accept());
+            let what = match stream.peer_addr() {
+                Ok(addr) => format!("accepted from {}", addr),
+                Err(_) => "accepted".to_owned()
+            };
+            Ok(self.log.wrap(stream, &what))
+        }
+
+        fn local_addr(&mut self) -> io::Result<SocketAddr> {
+            self.listener.local_addr()
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use mock::MockStream;
+    use testing::MockStream;
     use super::{NetworkStream};
 
     #[test]
@@ -700,5 +1769,169 @@ mod tests {
         let mock = unsafe { stream.downcast_unchecked::<MockStream>() };
         assert_eq!(mock, Box::new(MockStream::new()));
     }
-}
 
+    #[test]
+    fn test_socket_options() {
+        use std::time::Duration;
+        use net2::TcpStreamExt;
+        use super::{ConfiguredConnector, HttpListener, NetworkConnector, NetworkListener};
+        use super::SocketOptions;
+
+        let mut options = SocketOptions::default();
+        options.nodelay = Some(true);
+        options.keepalive = Some(Duration::from_secs(30));
+        options.local_addr = Some("127.0.0.1:0".parse().unwrap());
+
+        let mut listener = HttpListener::with_socket_options("127.0.0.1:0", options.clone())
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let connector = ConfiguredConnector::new(options);
+        let client = connector.connect("127.0.0.1", port, "http").unwrap();
+        let server = listener.accept().unwrap();
+
+        for stream in &[client, server] {
+            assert!(stream.0.nodelay().unwrap());
+            assert_eq!(stream.0.keepalive().unwrap(), Some(Duration::from_secs(30)));
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos",
+              target_os = "ios", target_os = "freebsd", target_os = "dragonfly",
+              target_os = "netbsd"))]
+    #[test]
+    fn test_keepalive_interval_and_retries() {
+        use std::mem;
+        use std::os::unix::io::AsRawFd;
+        use std::time::Duration;
+        use libc;
+        use super::{ConfiguredConnector, HttpListener, NetworkConnector, NetworkListener};
+        use super::SocketOptions;
+
+        fn get(stream: &::std::net::TcpStream, option: libc::c_int) -> libc::c_int {
+            let mut value: libc::c_int = 0;
+            let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+            let ret = unsafe {
+                libc::getsockopt(stream.as_raw_fd(), libc::IPPROTO_TCP, option,
+                                 &mut value as *mut libc::c_int as *mut libc::c_void, &mut len)
+            };
+            assert_eq!(ret, 0);
+            value
+        }
+
+        let mut options = SocketOptions::default();
+        options.keepalive = Some(Duration::from_secs(30));
+        options.keepalive_interval = Some(Duration::from_secs(5));
+        options.keepalive_retries = Some(3);
+
+        let mut listener = HttpListener::new("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut connector = ConfiguredConnector::new(options.clone());
+        let stream = connector.connect("127.0.0.1", port, "http").unwrap();
+        listener.accept().unwrap();
+
+        assert_eq!(get(&stream.0, libc::TCP_KEEPINTVL), 5);
+        assert_eq!(get(&stream.0, libc::TCP_KEEPCNT), 3);
+
+        // rounded up to whole seconds
+        options.keepalive_interval = Some(Duration::from_millis(500));
+        connector.set_socket_options(options);
+        let stream = connector.connect("127.0.0.1", port, "http").unwrap();
+        listener.accept().unwrap();
+        assert_eq!(get(&stream.0, libc::TCP_KEEPINTVL), 1);
+    }
+
+    #[test]
+    fn test_connect_timed() {
+        use super::{HttpConnector, HttpListener, NetworkConnector, NetworkListener};
+
+        let mut listener = HttpListener::new("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let connector = HttpConnector::default();
+        let (_, timing) = connector.connect_timed("127.0.0.1", port, "http").unwrap();
+        assert!(timing.pool.is_none());
+        assert!(timing.dns.is_some());
+        assert!(timing.connect.is_some());
+        assert!(timing.tls.is_none());
+        listener.accept().unwrap();
+    }
+
+    #[test]
+    fn test_wire_log() {
+        use std::io::{self, Read, Write};
+        use std::sync::{Arc, Mutex};
+        use super::{WireFormat, WireLog};
+
+        #[derive(Clone)]
+        struct Sink(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Sink {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let sink = Sink(Arc::new(Mutex::new(Vec::new())));
+        let mut log = WireLog::new(sink.clone(), WireFormat::Text);
+        log.set_redact(true);
+        let mut stream = log.wrap(MockStream::with_input(b"HTTP/1.1 200 OK\r\n\r\n"), "test");
+        assert_eq!(stream.id(), 1);
+        stream.write_all(b"GET / HTTP/1.1\r\nAuthorization: Basic c2VjcmV0\r\n\r\n\
+                           Cookie: kept in the body\n").unwrap();
+        let mut s = String::new();
+        stream.read_to_string(&mut s).unwrap();
+
+        let logged = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains("#1] test\n"));
+        assert!(logged.contains("#1 >] 74 bytes\n"));
+        assert!(logged.contains("    Authorization: [redacted]\\r\\n\n"));
+        assert!(!logged.contains("c2VjcmV0"));
+        assert!(logged.contains("    Cookie: kept in the body\\n\n"));
+        assert!(logged.contains("#1 <] 19 bytes\n    HTTP/1.1 200 OK\\r\\n\n"));
+
+        sink.0.lock().unwrap().clear();
+        let log = WireLog::new(sink.clone(), WireFormat::Hexdump);
+        let mut stream = log.wrap(MockStream::new(), "hex");
+        stream.write_all(b"hi\x00").unwrap();
+        let logged = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains("    00000000  68 69 00"));
+        assert!(logged.ends_with("|hi.|\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_connector() {
+        use std::io::{Read, Write};
+        use std::thread;
+        use testing::TempPath;
+        use super::{NetworkConnector, NetworkListener};
+        use super::{UnixSocketConnector, UnixSocketListener};
+
+        let path = TempPath::new("hyper-test-unix-socket");
+        let mut listener = UnixSocketListener::new(&path).unwrap();
+        assert_eq!(listener.local_addr().unwrap(), "0.0.0.0:0".parse().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut stream = listener.accept().unwrap();
+            stream.write_all(b"hello").unwrap();
+        });
+
+        let mut connector = UnixSocketConnector::default();
+        connector.add_host("sock", &path);
+        assert!(connector.connect("other", 80, "http").is_err());
+        assert!(connector.connect("sock", 80, "https").is_err());
+
+        let mut stream = connector.connect("sock", 80, "http").unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), "0.0.0.0:0".parse().unwrap());
+        let mut s = String::new();
+        stream.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "hello");
+
+        handle.join().unwrap();
+    }
+}
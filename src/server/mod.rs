@@ -110,6 +110,8 @@
 use std::fmt;
 use std::io::{self, ErrorKind, BufWriter, Write};
 use std::net::{SocketAddr, ToSocketAddrs};
+#[cfg(unix)]
+use std::path::Path;
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
@@ -126,6 +128,8 @@ use header::{Headers, Expect, Connection};
 use http;
 use method::Method;
 use net::{NetworkListener, NetworkStream, HttpListener, HttpsListener, Ssl};
+#[cfg(unix)]
+use net::UnixSocketListener;
 use status::StatusCode;
 use uri::RequestUri;
 use version::HttpVersion::Http11;
@@ -203,6 +207,15 @@ impl Server<HttpListener> {
     }
 }
 
+#[cfg(unix)]
+impl Server<UnixSocketListener> {
+    /// Creates a new server that will handle `UnixSocketStream`s on the
+    /// socket at `path`.
+    pub fn unix<P: AsRef<Path>>(path: P) -> ::Result<Server<UnixSocketListener>> {
+        UnixSocketListener::new(path).map(Server::new)
+    }
+}
+
 impl<S: Ssl + Clone + Send> Server<HttpsListener<S>> {
     /// Creates a new server that will handle `HttpStream`s over SSL.
     ///
@@ -347,6 +360,7 @@ impl<H: Handler + 'static> Worker<H> {
         let mut keep_alive = self.timeouts.keep_alive.is_some() &&
             http::should_keep_alive(req.version, &req.headers);
         let version = req.version;
+        let trailers_accepted = http::accepts_trailers(&req.headers);
         let mut res_headers = Headers::new();
         if !keep_alive {
             res_headers.set(Connection::close());
@@ -354,6 +368,7 @@ impl<H: Handler + 'static> Worker<H> {
         {
             let mut res = Response::new(wrt, &mut res_headers);
             res.version = version;
+            res.set_trailers_accepted(trailers_accepted);
             self.handler.handle(req, res);
         }
 
@@ -452,7 +467,7 @@ impl<F> Handler for F where F: Fn(Request, Response<Fresh>), F: Sync + Send {
 mod tests {
     use header::Headers;
     use method::Method;
-    use mock::MockStream;
+    use testing::MockStream;
     use status::StatusCode;
     use uri::RequestUri;
 
@@ -475,9 +490,10 @@ mod tests {
 
         Worker::new(handle, Default::default()).handle_connection(&mut mock);
         let cont = b"HTTP/1.1 100 Continue\r\n\r\n";
-        assert_eq!(&mock.write[..cont.len()], cont);
+        let written = mock.written();
+        assert_eq!(&written[..cont.len()], cont);
         let res = b"HTTP/1.1 200 OK\r\n";
-        assert_eq!(&mock.write[cont.len()..cont.len() + res.len()], res);
+        assert_eq!(&written[cont.len()..cont.len() + res.len()], res);
     }
 
     #[test]
@@ -503,6 +519,6 @@ mod tests {
         ");
 
         Worker::new(Reject, Default::default()).handle_connection(&mut mock);
-        assert_eq!(mock.write, &b"HTTP/1.1 417 Expectation Failed\r\n\r\n"[..]);
+        assert_eq!(mock.written(), &b"HTTP/1.1 417 Expectation Failed\r\n\r\n"[..]);
     }
 }
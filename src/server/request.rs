@@ -11,7 +11,8 @@ use net::NetworkStream;
 use version::{HttpVersion};
 use method::Method::{self, Get, Head};
 use header::{Headers, ContentLength, TransferEncoding};
-use http::h1::{self, Incoming, HttpReader};
+use http::Chunk;
+use http::h1::{self, Incoming, HttpReader, TrailersReader};
 use http::h1::HttpReader::{SizedReader, ChunkedReader, EmptyReader};
 use uri::RequestUri;
 
@@ -27,7 +28,7 @@ pub struct Request<'a, 'b: 'a> {
     pub uri: RequestUri,
     /// The version of HTTP for this request.
     pub version: HttpVersion,
-    body: HttpReader<&'a mut BufReader<&'b mut NetworkStream>>
+    body: TrailersReader<&'a mut BufReader<&'b mut NetworkStream>>
 }
 
 
@@ -61,7 +62,7 @@ impl<'a, 'b: 'a> Request<'a, 'b> {
             uri: uri,
             headers: headers,
             version: version,
-            body: body
+            body: TrailersReader::new(body)
         })
     }
 
@@ -69,7 +70,7 @@ impl<'a, 'b: 'a> Request<'a, 'b> {
     #[cfg(feature = "timeouts")]
     #[inline]
     pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
-        self.body.get_ref().get_ref().set_read_timeout(timeout)
+        self.body.get_ref().get_ref().get_ref().set_read_timeout(timeout)
     }
 
     /// Set the read timeout of the underlying NetworkStream.
@@ -81,7 +82,7 @@ impl<'a, 'b: 'a> Request<'a, 'b> {
     /// Get a reference to the underlying `NetworkStream`.
     #[inline]
     pub fn downcast_ref<T: NetworkStream>(&self) -> Option<&T> {
-        self.body.get_ref().get_ref().downcast_ref()
+        self.body.get_ref().get_ref().get_ref().downcast_ref()
     }
 
     /// Get a reference to the underlying Ssl stream, if connected
@@ -111,13 +112,29 @@ impl<'a, 'b: 'a> Request<'a, 'b> {
         }
     }
 
+    /// Read the next whole chunk of a chunked body, with its extensions.
+    ///
+    /// See `HttpReader::read_chunk`.
+    #[inline]
+    pub fn read_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        self.body.read_chunk()
+    }
+
+    /// Get the trailers sent after a chunked body.
+    ///
+    /// These are only available once the body has been read to the end.
+    #[inline]
+    pub fn trailers(&self) -> Option<&Headers> {
+        self.body.trailers()
+    }
+
     /// Deconstruct a Request into its constituent parts.
     #[inline]
     pub fn deconstruct(self) -> (SocketAddr, Method, Headers,
                                  RequestUri, HttpVersion,
                                  HttpReader<&'a mut BufReader<&'b mut NetworkStream>>) {
         (self.remote_addr, self.method, self.headers,
-         self.uri, self.version, self.body)
+         self.uri, self.version, self.body.into_inner())
     }
 }
 
@@ -133,7 +150,7 @@ mod tests {
     use buffer::BufReader;
     use header::{Host, TransferEncoding, Encoding};
     use net::NetworkStream;
-    use mock::MockStream;
+    use testing::MockStream;
     use super::Request;
 
     use std::io::{self, Read};
@@ -241,6 +258,32 @@ mod tests {
         assert_eq!(read_to_string(req).unwrap(), "qwert".to_owned());
     }
 
+    #[test]
+    fn test_parse_chunked_request_trailers() {
+        let mut mock = MockStream::with_input(b"\
+            POST / HTTP/1.1\r\n\
+            Host: example.domain\r\n\
+            Transfer-Encoding: chunked\r\n\
+            \r\n\
+            1\r\n\
+            q\r\n\
+            0\r\n\
+            Content-MD5: abc\r\n\
+            \r\n"
+        );
+
+        // FIXME: Use Type ascription
+        let mock: &mut NetworkStream = &mut mock;
+        let mut stream = BufReader::new(mock);
+
+        let mut req = Request::new(&mut stream, sock("127.0.0.1:80")).unwrap();
+        let mut s = String::new();
+        req.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "q");
+        assert_eq!(req.trailers().unwrap().get_raw("Content-MD5").unwrap(),
+                   &[b"abc".to_vec()][..]);
+    }
+
     /// Tests that when a chunk size is not a valid radix-16 number, an error
     /// is returned.
     #[test]
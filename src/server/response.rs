@@ -36,6 +36,10 @@ pub struct Response<'a, W: Any = Fresh> {
     status: status::StatusCode,
     // The outgoing headers on this response.
     headers: &'a mut header::Headers,
+    // The trailers to write after a chunked body.
+    trailers: Option<header::Headers>,
+    // Whether the request had `TE: trailers`.
+    trailers_accepted: bool,
 
     _writing: PhantomData<W>
 }
@@ -60,6 +64,8 @@ impl<'a, W: Any> Response<'a, W> {
             version: version,
             body: body,
             headers: headers,
+            trailers: None,
+            trailers_accepted: false,
             _writing: PhantomData,
         }
     }
@@ -75,6 +81,7 @@ impl<'a, W: Any> Response<'a, W> {
                 self.status,
                 ptr::read(&self.headers)
             );
+            drop(ptr::read(&self.trailers));
             mem::forget(self);
             parts
         }
@@ -135,6 +142,8 @@ impl<'a> Response<'a, Fresh> {
             version: version::HttpVersion::Http11,
             headers: headers,
             body: ThroughWriter(stream),
+            trailers: None,
+            trailers_accepted: false,
             _writing: PhantomData,
         }
     }
@@ -178,6 +187,7 @@ impl<'a> Response<'a, Fresh> {
     /// creating a Response<Streaming>
     pub fn start(mut self) -> io::Result<Response<'a, Streaming>> {
         let body_type = try!(self.write_head());
+        let trailers_accepted = self.trailers_accepted;
         let (version, body, status, headers) = self.deconstruct();
         let stream = match body_type {
             Body::Chunked => ChunkedWriter(body.into_inner()),
@@ -191,6 +201,8 @@ impl<'a> Response<'a, Fresh> {
             body: stream,
             status: status,
             headers: headers,
+            trailers: None,
+            trailers_accepted: trailers_accepted,
             _writing: PhantomData,
         })
     }
@@ -201,16 +213,58 @@ impl<'a> Response<'a, Fresh> {
     /// Get a mutable reference to the Headers.
     #[inline]
     pub fn headers_mut(&mut self) -> &mut header::Headers { self.headers }
+
+    /// Set whether the client accepts trailers, as it does when the request
+    /// has a `TE: trailers` header.
+    ///
+    /// The `Server` sets this from each request.
+    #[inline]
+    pub fn set_trailers_accepted(&mut self, accepted: bool) {
+        self.trailers_accepted = accepted;
+    }
 }
 
 
 impl<'a> Response<'a, Streaming> {
+    /// Sets trailers to send after the body, such as a checksum of it, or
+    /// `Server-Timing`.
+    ///
+    /// Trailers can only follow a chunked body, and are only sent if the
+    /// request had a `TE: trailers` header. Returns whether they will be sent.
+    pub fn set_trailers(&mut self, trailers: header::Headers) -> bool {
+        let allowed = match self.body {
+            ChunkedWriter(..) => self.trailers_accepted,
+            _ => false
+        };
+        if allowed {
+            self.trailers = Some(trailers);
+        } else {
+            debug!("trailers not allowed, dropping {:?}", trailers);
+        }
+        allowed
+    }
+
+    /// Writes `data` as exactly one chunk of a chunked body, with the given
+    /// extensions.
+    ///
+    /// See `HttpWriter::write_chunk`.
+    #[inline]
+    pub fn write_chunk(&mut self, data: &[u8], extensions: &[(&str, Option<&str>)])
+            -> io::Result<()> {
+        debug!("write_chunk {:?} bytes", data.len());
+        self.body.write_chunk(data, extensions)
+    }
+
     /// Flushes all writing of a response to the client.
     #[inline]
-    pub fn end(self) -> io::Result<()> {
+    pub fn end(mut self) -> io::Result<()> {
         trace!("ending");
+        let trailers = self.trailers.take();
         let (_, body, _, _) = self.deconstruct();
-        try!(body.end());
+        match trailers {
+            Some(ref trailers) => try!(body.end_with_trailers(trailers)),
+            None => try!(body.end())
+        };
         Ok(())
     }
 }
@@ -252,6 +306,11 @@ impl<'a, T: Any> Drop for Response<'a, T> {
                 }
             };
             end(&mut body);
+        } else if let Some(trailers) = self.trailers.take() {
+            match self.body.write_trailers(&trailers) {
+                Ok(_) => debug!("drop successful"),
+                Err(e) => debug!("error dropping request: {:?}", e)
+            }
         } else {
             end(&mut self.body);
         };
@@ -273,12 +332,12 @@ impl<'a, T: Any> Drop for Response<'a, T> {
 #[cfg(test)]
 mod tests {
     use header::Headers;
-    use mock::MockStream;
+    use testing::MockStream;
     use super::Response;
 
     macro_rules! lines {
         ($s:ident = $($line:pat),+) => ({
-            let s = String::from_utf8($s.write).unwrap();
+            let s = String::from_utf8($s.written()).unwrap();
             let mut lines = s.split_terminator("\r\n");
 
             $(
@@ -328,6 +387,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_streaming_end_trailers() {
+        use std::io::Write;
+        let mut trailers = Headers::new();
+        trailers.set_raw("Content-MD5", vec![b"abc".to_vec()]);
+
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        {
+            let mut res = Response::new(&mut stream, &mut headers);
+            res.set_trailers_accepted(true);
+            let mut res = res.start().unwrap();
+            res.write_all(b"foo").unwrap();
+            assert!(res.set_trailers(trailers.clone()));
+            res.end().unwrap();
+        }
+
+        lines! { stream =
+            "HTTP/1.1 200 OK",
+            _date,
+            _transfer_encoding,
+            "",
+            "3",
+            "foo",
+            "0",
+            "Content-MD5: abc",
+            ""
+        }
+
+        // without `TE: trailers`, they are dropped
+        let mut headers = Headers::new();
+        let mut stream = MockStream::new();
+        {
+            let res = Response::new(&mut stream, &mut headers);
+            let mut res = res.start().unwrap();
+            assert!(!res.set_trailers(trailers));
+            res.end().unwrap();
+        }
+
+        lines! { stream =
+            "HTTP/1.1 200 OK",
+            _date,
+            _transfer_encoding,
+            "",
+            "0",
+            ""
+        }
+    }
+
     #[test]
     fn test_fresh_drop() {
         use status::StatusCode;
@@ -0,0 +1,354 @@
+//! Fault injection for network streams.
+//!
+//! A `FaultConnector` wraps another connector, and makes the streams it
+//! connects behave like a bad network: slow, short of bandwidth, returning
+//! short reads and writes, reset after some bytes, or stalled.
+//!
+//! Faults are configured per host. Anything random is drawn from a generator
+//! seeded by `Faults::seed` and how many connections to that host came
+//! before, so a test run can be repeated exactly.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use hyper::Client;
+//! use hyper::net::HttpConnector;
+//! use hyper::testing::fault::{FaultConnector, Faults};
+//!
+//! let mut connector = FaultConnector::new(HttpConnector::default());
+//! connector.set_faults("flaky.domain", Faults {
+//!     latency: Some(Duration::from_millis(200)),
+//!     short_reads: true,
+//!     reset_after: Some(4096),
+//!     seed: 42,
+//!     ..Faults::default()
+//! });
+//! let client = Client::with_connector(connector);
+//! ```
+use std::collections::HashMap;
+use std::cell::Cell;
+use std::cmp;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, Shutdown};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use net::{ConnectTiming, HttpConnector, NetworkConnector, NetworkStream, StreamCloser,
+          TlsInfo};
+
+/// The faults injected into a stream.
+///
+/// The default injects none.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Faults {
+    /// A delay before connecting, and before the first read after a write,
+    /// like a round trip over a slow network.
+    pub latency: Option<Duration>,
+    /// How many bytes per second can be read or written.
+    pub bandwidth: Option<u64>,
+    /// Whether reads return a random part of what they could.
+    pub short_reads: bool,
+    /// Whether writes take a random part of what they are given.
+    pub short_writes: bool,
+    /// Reset the connection once this many bytes were read and written.
+    pub reset_after: Option<u64>,
+    /// Stall reads once this many bytes were read.
+    ///
+    /// A stalled read waits for the read timeout of the stream, or else the
+    /// `stall` duration, and then fails as timed out.
+    pub stall_after: Option<u64>,
+    /// How long a stalled read waits if the stream has no read timeout.
+    ///
+    /// Defaults to 30 seconds.
+    pub stall: Duration,
+    /// The seed of the random choices.
+    pub seed: u64,
+}
+
+impl Default for Faults {
+    fn default() -> Faults {
+        Faults {
+            latency: None,
+            bandwidth: None,
+            short_reads: false,
+            short_writes: false,
+            reset_after: None,
+            stall_after: None,
+            stall: Duration::from_secs(30),
+            seed: 0,
+        }
+    }
+}
+
+/// A `NetworkConnector` injecting `Faults` into the streams of another.
+pub struct FaultConnector<C = HttpConnector> {
+    connector: C,
+    hosts: HashMap<String, Faults>,
+    default: Faults,
+    connections: Mutex<HashMap<String, u64>>,
+}
+
+impl<C: NetworkConnector> FaultConnector<C> {
+    /// Wrap `connector`, without any faults yet.
+    pub fn new(connector: C) -> FaultConnector<C> {
+        FaultConnector {
+            connector: connector,
+            hosts: HashMap::new(),
+            default: Faults::default(),
+            connections: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the faults of connections to `host`.
+    pub fn set_faults(&mut self, host: &str, faults: Faults) {
+        self.hosts.insert(host.to_owned(), faults);
+    }
+
+    /// Set the faults of connections to hosts without faults of their own.
+    pub fn set_default_faults(&mut self, faults: Faults) {
+        self.default = faults;
+    }
+}
+
+impl<C: NetworkConnector> NetworkConnector for FaultConnector<C> {
+    type Stream = FaultStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<FaultStream> {
+        let faults = self.hosts.get(host).unwrap_or(&self.default).clone();
+        let nth = {
+            let mut connections = self.connections.lock().unwrap();
+            let count = connections.entry(host.to_owned()).or_insert(0);
+            *count += 1;
+            *count - 1
+        };
+        if let Some(latency) = faults.latency {
+            thread::sleep(latency);
+        }
+        let stream = try!(self.connector.connect(host, port, scheme));
+        let mut stream = FaultStream::new(stream, faults);
+        stream.rng = Rng::new(stream.faults.seed.wrapping_add(nth));
+        Ok(stream)
+    }
+}
+
+/// A stream with `Faults` injected.
+pub struct FaultStream {
+    inner: Box<NetworkStream + Send>,
+    faults: Faults,
+    rng: Rng,
+    read: u64,
+    written: u64,
+    // a write happened since the last read
+    awaiting: bool,
+    read_timeout: Cell<Option<Duration>>,
+}
+
+impl FaultStream {
+    /// Wrap `stream` to inject `faults` into it.
+    pub fn new<S: Into<Box<NetworkStream + Send>>>(stream: S, faults: Faults) -> FaultStream {
+        FaultStream {
+            inner: stream.into(),
+            rng: Rng::new(faults.seed),
+            faults: faults,
+            read: 0,
+            written: 0,
+            awaiting: false,
+            read_timeout: Cell::new(None),
+        }
+    }
+
+    /// The faults injected into this stream.
+    pub fn faults(&self) -> &Faults {
+        &self.faults
+    }
+
+    // How much of `len` bytes may be transferred now, or an error if the
+    // connection is reset.
+    fn allowance(&mut self, len: usize, short: bool) -> io::Result<usize> {
+        let mut len = len;
+        if let Some(limit) = self.faults.reset_after {
+            let remaining = limit.saturating_sub(self.read + self.written);
+            if remaining == 0 && len > 0 {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset,
+                                          "connection reset by fault injection"));
+            }
+            len = cmp::min(len as u64, remaining) as usize;
+        }
+        if short && len > 1 {
+            len = 1 + (self.rng.next() % len as u64) as usize;
+        }
+        Ok(len)
+    }
+
+    fn throttle(&self, n: usize) {
+        if let Some(bps) = self.faults.bandwidth {
+            if bps > 0 && n > 0 {
+                let nanos = n as u64 * 1_000_000_000 / bps;
+                thread::sleep(Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32));
+            }
+        }
+    }
+}
+
+impl Read for FaultStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.faults.stall_after.map_or(false, |after| self.read >= after) {
+            thread::sleep(self.read_timeout.get().unwrap_or(self.faults.stall));
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "read stalled by fault injection"));
+        }
+        if self.awaiting {
+            self.awaiting = false;
+            if let Some(latency) = self.faults.latency {
+                thread::sleep(latency);
+            }
+        }
+        let short = self.faults.short_reads;
+        let mut len = try!(self.allowance(buf.len(), short));
+        if let Some(after) = self.faults.stall_after {
+            len = cmp::min(len as u64, after - self.read) as usize;
+        }
+        let n = try!(self.inner.read(&mut buf[..len]));
+        self.read += n as u64;
+        self.throttle(n);
+        Ok(n)
+    }
+}
+
+impl Write for FaultStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let short = self.faults.short_writes;
+        let len = try!(self.allowance(buf.len(), short));
+        let n = try!(self.inner.write(&buf[..len]));
+        self.written += n as u64;
+        self.awaiting = true;
+        self.throttle(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl NetworkStream for FaultStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    #[cfg(feature = "timeouts")]
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.read_timeout.set(dur);
+        self.inner.set_read_timeout(dur)
+    }
+
+    #[cfg(feature = "timeouts")]
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_write_timeout(dur)
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.inner.close(how)
+    }
+
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        self.inner.closer()
+    }
+
+    fn tls_info(&self) -> Option<TlsInfo> {
+        self.inner.tls_info()
+    }
+
+    fn connect_timing(&self) -> Option<ConnectTiming> {
+        self.inner.connect_timing()
+    }
+}
+
+// A xorshift generator; good enough to pick sizes, and the same everywhere.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // xorshift must not start from 0
+        Rng(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+    use std::time::Duration;
+
+    use net::NetworkConnector;
+    use testing::{MockConnector, MockStream};
+    use super::{FaultConnector, FaultStream, Faults};
+
+    #[test]
+    fn test_short_reads_are_deterministic() {
+        let faults = Faults { short_reads: true, seed: 7, ..Faults::default() };
+        let sizes = || {
+            let mut stream = FaultStream::new(MockStream::with_input(&[0; 64]), faults.clone());
+            let mut buf = [0; 16];
+            let mut sizes = Vec::new();
+            loop {
+                match stream.read(&mut buf).unwrap() {
+                    0 => return sizes,
+                    n => sizes.push(n)
+                }
+            }
+        };
+        let first = sizes();
+        assert_eq!(first.iter().fold(0, |sum, n| sum + n), 64);
+        assert!(first.iter().all(|&n| n <= 16));
+        assert_eq!(first, sizes());
+    }
+
+    #[test]
+    fn test_reset_after() {
+        let faults = Faults { reset_after: Some(6), ..Faults::default() };
+        let mut stream = FaultStream::new(MockStream::with_input(b"hello world"), faults);
+        assert_eq!(stream.write(b"ping").unwrap(), 4);
+        let mut buf = [0; 8];
+        assert_eq!(stream.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"he");
+        assert_eq!(stream.read(&mut buf).unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(stream.write(b"ping").unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+    }
+
+    #[test]
+    fn test_stall_after() {
+        let faults = Faults {
+            stall_after: Some(5),
+            stall: Duration::from_millis(1),
+            ..Faults::default()
+        };
+        let mut stream = FaultStream::new(MockStream::with_input(b"hello world"), faults);
+        let mut buf = [0; 8];
+        assert_eq!(stream.read(&mut buf).unwrap(), 5);
+        assert_eq!(stream.read(&mut buf).unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_faults_per_host() {
+        let mut mock = MockConnector::new();
+        mock.add_response("http://good.domain", "hello");
+        mock.add_response("http://bad.domain", "hello");
+        let mut connector = FaultConnector::new(mock);
+        connector.set_faults("bad.domain", Faults { reset_after: Some(0), ..Faults::default() });
+
+        let mut s = String::new();
+        connector.connect("good.domain", 80, "http").unwrap().read_to_string(&mut s).unwrap();
+        assert_eq!(s, "hello");
+        let mut bad = connector.connect("bad.domain", 80, "http").unwrap();
+        assert!(bad.read_to_string(&mut s).is_err());
+    }
+}
@@ -0,0 +1,743 @@
+//! Utilities for testing code that uses hyper.
+//!
+//! This module is only available with the `testing` feature enabled.
+//!
+//! It provides a `MockStream` and `MockConnector` to feed canned responses to
+//! a `Client`, and an in-memory `pipe()` to connect a real `Client` and
+//! `Server` to each other without any sockets:
+//!
+//! ```
+//! use std::io::Read;
+//! use hyper::{Client, Server};
+//! use hyper::server::{Request, Response};
+//! use hyper::testing::PipeListener;
+//!
+//! let listener = PipeListener::new();
+//! let client = Client::with_connector(listener.connector());
+//! let mut listening = Server::new(listener).handle(|_: Request, res: Response| {
+//!     res.send(b"hello").unwrap();
+//! }).unwrap();
+//!
+//! let mut body = String::new();
+//! client.get("http://example.domain/").send().unwrap().read_to_string(&mut body).unwrap();
+//! assert_eq!(body, "hello");
+//! listening.close().unwrap();
+//! ```
+//!
+//! The `fault` module makes streams misbehave like a bad network.
+use std::ascii::AsciiExt;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, Shutdown};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::time::Duration;
+
+use solicit::http::HttpScheme;
+use solicit::http::transport::TransportStream;
+use solicit::http::frame::{SettingsFrame, Frame};
+use solicit::http::connection::{HttpConnection, EndStream, DataChunk};
+
+use time::precise_time_ns;
+
+use header::Headers;
+use net::{NetworkConnector, NetworkListener, NetworkStream, StreamCloser};
+
+pub mod fault;
+
+/// new connectors must be created if you wish to intercept requests.
+macro_rules! mock_connector (
+    ($name:ident {
+        $($url:expr => $res:expr)*
+    }) => (
+
+        struct $name;
+
+        impl $crate::net::NetworkConnector for $name {
+            type Stream = $crate::testing::MockStream;
+            fn connect(&self, host: &str, port: u16, scheme: &str)
+                    -> $crate::Result<$crate::testing::MockStream> {
+                use std::collections::HashMap;
+                debug!("MockStream::connect({:?}, {:?}, {:?})", host, port, scheme);
+                let mut map = HashMap::new();
+                $(map.insert($url, $res);)*
+
+
+                let key = format!("{}://{}", scheme, host);
+                // ignore port for now
+                match map.get(&*key) {
+                    Some(&res) => Ok($crate::testing::MockStream::with_input(res.as_bytes())),
+                    None => panic!("{:?} doesn't know url {}", stringify!($name), key)
+                }
+            }
+        }
+
+    );
+
+    ($name:ident { $($response:expr),+ }) => (
+        struct $name;
+
+        impl $crate::net::NetworkConnector for $name {
+            type Stream = $crate::testing::MockStream;
+            fn connect(&self, _: &str, _: u16, _: &str)
+                    -> $crate::Result<$crate::testing::MockStream> {
+                Ok($crate::testing::MockStream::with_responses(vec![
+                    $($response),+
+                ]))
+            }
+        }
+    );
+);
+
+/// A `NetworkStream` that reads canned input, and keeps everything written
+/// to it.
+///
+/// Clones of a `MockStream` share the same state, so a test can keep a
+/// clone to inspect after giving the stream away.
+#[derive(Clone, Debug)]
+pub struct MockStream {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    input: Vec<u8>,
+    pos: usize,
+    next_inputs: VecDeque<Vec<u8>>,
+    written: Vec<u8>,
+    closed: bool,
+    fail_reads: bool,
+    fail_writes: bool,
+    #[cfg(feature = "timeouts")]
+    read_timeout: Option<Duration>,
+    #[cfg(feature = "timeouts")]
+    write_timeout: Option<Duration>,
+}
+
+impl MockStream {
+    /// Create a stream with nothing to read.
+    pub fn new() -> MockStream {
+        MockStream::with_input(b"")
+    }
+
+    /// Create a stream that reads `input`, and then EOF.
+    pub fn with_input(input: &[u8]) -> MockStream {
+        MockStream::with_responses(vec![input])
+    }
+
+    /// Create a stream that reads each of `responses` in turn, such as
+    /// several responses on a keep-alive connection.
+    pub fn with_responses(responses: Vec<&[u8]>) -> MockStream {
+        let mut state = MockState::default();
+        state.next_inputs = responses.into_iter().map(|input| input.to_vec()).collect();
+        MockStream {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Create a stream that reads the response described by the parameters
+    /// as an HTTP/2 response, including the server preface.
+    pub fn new_http2_response(status: &[u8], headers: &Headers, body: Option<Vec<u8>>)
+            -> MockStream {
+        MockStream::with_input(&build_http2_response(status, headers, body))
+    }
+
+    /// Get a copy of everything written to this stream so far.
+    pub fn written(&self) -> Vec<u8> {
+        self.state.lock().unwrap().written.clone()
+    }
+
+    /// Whether this stream has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// Make every following read fail with an error.
+    pub fn fail_reads(&self, fail: bool) {
+        self.state.lock().unwrap().fail_reads = fail;
+    }
+
+    /// Make every following write fail with an error.
+    pub fn fail_writes(&self, fail: bool) {
+        self.state.lock().unwrap().fail_writes = fail;
+    }
+
+    /// The read timeout last set on this stream.
+    #[cfg(feature = "timeouts")]
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.state.lock().unwrap().read_timeout
+    }
+
+    /// The write timeout last set on this stream.
+    #[cfg(feature = "timeouts")]
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.state.lock().unwrap().write_timeout
+    }
+}
+
+impl PartialEq for MockStream {
+    fn eq(&self, other: &MockStream) -> bool {
+        let (input, next_inputs, written) = {
+            let state = self.state.lock().unwrap();
+            (state.input[state.pos..].to_vec(), state.next_inputs.clone(), state.written.clone())
+        };
+        let other = other.state.lock().unwrap();
+        input == &other.input[other.pos..] && next_inputs == other.next_inputs &&
+            written == other.written
+    }
+}
+
+impl Read for MockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.fail_reads {
+            return Err(io::Error::new(io::ErrorKind::Other, "mock error"));
+        }
+        if state.pos == state.input.len() {
+            if let Some(input) = state.next_inputs.pop_front() {
+                state.input = input;
+                state.pos = 0;
+            }
+        }
+        let n = try!((&state.input[state.pos..]).read(buf));
+        state.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for MockStream {
+    fn write(&mut self, msg: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.fail_writes {
+            return Err(io::Error::new(io::ErrorKind::Other, "mock error"));
+        }
+        state.written.extend(msg.iter().cloned());
+        Ok(msg.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl NetworkStream for MockStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Ok("127.0.0.1:1337".parse().unwrap())
+    }
+
+    #[cfg(feature = "timeouts")]
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.state.lock().unwrap().read_timeout = dur;
+        Ok(())
+    }
+
+    #[cfg(feature = "timeouts")]
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.state.lock().unwrap().write_timeout = dur;
+        Ok(())
+    }
+
+    fn close(&mut self, _how: Shutdown) -> io::Result<()> {
+        self.state.lock().unwrap().closed = true;
+        Ok(())
+    }
+}
+
+impl TransportStream for MockStream {
+    fn try_split(&self) -> Result<MockStream, io::Error> {
+        Ok(self.clone())
+    }
+
+    fn close(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+// Builds the bytes of a server's HTTP/2 response, described by the parameters.
+fn build_http2_response(status: &[u8], headers: &Headers, body: Option<Vec<u8>>) -> Vec<u8> {
+    let mut conn = HttpConnection::new(MockStream::new(), MockStream::new(), HttpScheme::Http);
+    // Server preface first
+    conn.sender.write(&SettingsFrame::new().serialize()).unwrap();
+
+    let mut resp_headers: Vec<_> = headers.iter().map(|h| {
+        (h.name().to_ascii_lowercase().into_bytes(), h.value_string().into_bytes())
+    }).collect();
+    resp_headers.insert(0, (b":status".to_vec(), status.into()));
+
+    let end = if body.is_none() {
+        EndStream::Yes
+    } else {
+        EndStream::No
+    };
+    conn.send_headers(resp_headers, 1, end).unwrap();
+    if body.is_some() {
+        let chunk = DataChunk::new_borrowed(&body.as_ref().unwrap()[..], 1, EndStream::Yes);
+        conn.send_data(chunk).unwrap();
+    }
+
+    conn.sender.written()
+}
+
+/// A `NetworkConnector` that connects to `MockStream`s reading canned
+/// responses.
+///
+/// Responses are added per origin, such as `"http://127.0.0.1"`. The port is
+/// ignored. Connecting to an unknown origin fails as connection refused.
+///
+/// Clones of a `MockConnector` share the streams they connect.
+#[derive(Clone, Debug, Default)]
+pub struct MockConnector {
+    responses: HashMap<String, Vec<Vec<u8>>>,
+    streams: Arc<Mutex<Vec<MockStream>>>,
+}
+
+impl MockConnector {
+    /// Create a connector that doesn't know any origin.
+    pub fn new() -> MockConnector {
+        MockConnector::default()
+    }
+
+    /// Add a response for connections to `origin`.
+    ///
+    /// Every connection to `origin` reads all of its responses in the order
+    /// they were added.
+    pub fn add_response<R: Into<Vec<u8>>>(&mut self, origin: &str, response: R) {
+        self.responses.entry(origin.to_owned()).or_insert(Vec::new()).push(response.into());
+    }
+
+    /// Get the streams connected so far, to inspect what was written to them.
+    pub fn streams(&self) -> Vec<MockStream> {
+        self.streams.lock().unwrap().clone()
+    }
+}
+
+impl NetworkConnector for MockConnector {
+    type Stream = MockStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<MockStream> {
+        debug!("MockConnector::connect({:?}, {:?}, {:?})", host, port, scheme);
+        let origin = format!("{}://{}", scheme, host);
+        let stream = match self.responses.get(&origin) {
+            Some(responses) => MockStream::with_responses(responses.iter().map(|res| &res[..]).collect()),
+            None => return Err(io::Error::new(io::ErrorKind::ConnectionRefused,
+                                              format!("no mock responses for {}", origin)).into())
+        };
+        self.streams.lock().unwrap().push(stream.clone());
+        Ok(stream)
+    }
+}
+
+/// A mock connector that produces `MockStream`s that are set to return HTTP/2 responses.
+///
+/// This means that the streams' payloads are fairly opaque byte sequences (as HTTP/2 is a binary
+/// protocol), which can be understood only be HTTP/2 clients.
+pub struct MockHttp2Connector {
+    streams: RefCell<Vec<MockStream>>,
+}
+
+impl MockHttp2Connector {
+    /// Creates a new `MockHttp2Connector` with no streams.
+    pub fn new() -> MockHttp2Connector {
+        MockHttp2Connector {
+            streams: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Adds a new `MockStream` to the end of the connector's stream queue.
+    ///
+    /// Streams are returned in a FIFO manner.
+    pub fn add_stream(&mut self, stream: MockStream) {
+        self.streams.borrow_mut().push(stream);
+    }
+
+    /// Adds a new response stream that will be placed to the end of the connector's stream queue.
+    ///
+    /// Returns a clone of the stream, to inspect what is written to it.
+    pub fn new_response_stream(&mut self, status: &[u8], headers: &Headers, body: Option<Vec<u8>>)
+            -> MockStream {
+        let stream = MockStream::new_http2_response(status, headers, body);
+        self.add_stream(stream.clone());
+        stream
+    }
+}
+
+impl NetworkConnector for MockHttp2Connector {
+    type Stream = MockStream;
+    #[inline]
+    fn connect(&self, _host: &str, _port: u16, _scheme: &str) -> ::Result<MockStream> {
+        Ok(self.streams.borrow_mut().remove(0))
+    }
+}
+
+/// Create a connected pair of in-memory streams.
+///
+/// Everything written to one end can be read from the other. A read blocks
+/// until there is data, or the other end is closed or dropped.
+pub fn pipe() -> (PipeStream, PipeStream) {
+    let a = Arc::new(Pipe::new());
+    let b = Arc::new(Pipe::new());
+    (PipeStream::new(a.clone(), b.clone()), PipeStream::new(b, a))
+}
+
+/// One end of an in-memory `pipe()`.
+///
+/// Clones of a `PipeStream` are handles to the same end, like a cloned
+/// `TcpStream`. The other end sees EOF once all of them are dropped.
+#[derive(Clone)]
+pub struct PipeStream {
+    end: Arc<PipeEnd>,
+}
+
+struct PipeEnd {
+    incoming: Arc<Pipe>,
+    outgoing: Arc<Pipe>,
+    read_timeout: Mutex<Option<Duration>>,
+}
+
+impl PipeStream {
+    fn new(incoming: Arc<Pipe>, outgoing: Arc<Pipe>) -> PipeStream {
+        PipeStream {
+            end: Arc::new(PipeEnd {
+                incoming: incoming,
+                outgoing: outgoing,
+                read_timeout: Mutex::new(None),
+            })
+        }
+    }
+}
+
+impl Drop for PipeEnd {
+    fn drop(&mut self) {
+        self.incoming.close();
+        self.outgoing.close();
+    }
+}
+
+impl Read for PipeStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timeout = *self.end.read_timeout.lock().unwrap();
+        self.end.incoming.read(buf, timeout)
+    }
+}
+
+impl Write for PipeStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.end.outgoing.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl NetworkStream for PipeStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Ok("127.0.0.1:0".parse().unwrap())
+    }
+
+    #[cfg(feature = "timeouts")]
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        *self.end.read_timeout.lock().unwrap() = dur;
+        Ok(())
+    }
+
+    // writes never block, as the pipe isn't bounded
+    #[cfg(feature = "timeouts")]
+    fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        StreamCloser::close(&self.end, how)
+    }
+
+    fn closer(&self) -> Option<Box<StreamCloser>> {
+        Some(Box::new(self.end.clone()))
+    }
+}
+
+impl StreamCloser for Arc<PipeEnd> {
+    fn close(&self, how: Shutdown) -> io::Result<()> {
+        match how {
+            Shutdown::Read => self.incoming.close(),
+            Shutdown::Write => self.outgoing.close(),
+            Shutdown::Both => {
+                self.incoming.close();
+                self.outgoing.close();
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Pipe {
+    state: Mutex<PipeState>,
+    readable: Condvar,
+}
+
+struct PipeState {
+    buf: VecDeque<u8>,
+    closed: bool,
+}
+
+impl Pipe {
+    fn new() -> Pipe {
+        Pipe {
+            state: Mutex::new(PipeState {
+                buf: VecDeque::new(),
+                closed: false,
+            }),
+            readable: Condvar::new(),
+        }
+    }
+
+    fn read(&self, buf: &mut [u8], timeout: Option<Duration>) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        while state.buf.is_empty() && !state.closed && !buf.is_empty() {
+            state = match timeout {
+                Some(dur) => {
+                    let (state, result) = self.readable.wait_timeout(state, dur).unwrap();
+                    if result.timed_out() && state.buf.is_empty() && !state.closed {
+                        return Err(io::Error::new(io::ErrorKind::TimedOut, "pipe read timed out"));
+                    }
+                    state
+                },
+                None => self.readable.wait(state).unwrap()
+            };
+        }
+        let mut n = 0;
+        while n < buf.len() {
+            match state.buf.pop_front() {
+                Some(byte) => buf[n] = byte,
+                None => break
+            }
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"));
+        }
+        state.buf.extend(buf.iter().cloned());
+        self.readable.notify_all();
+        Ok(buf.len())
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.readable.notify_all();
+    }
+}
+
+/// A `NetworkListener` accepting in-memory connections from its
+/// `PipeConnector`s.
+///
+/// Clones of a `PipeListener` accept from the same queue of connections.
+#[derive(Clone)]
+pub struct PipeListener {
+    queue: Arc<Backlog>,
+}
+
+struct Backlog {
+    pending: Mutex<VecDeque<PipeStream>>,
+    ready: Condvar,
+}
+
+impl PipeListener {
+    /// Create a listener with no connections yet.
+    pub fn new() -> PipeListener {
+        PipeListener {
+            queue: Arc::new(Backlog {
+                pending: Mutex::new(VecDeque::new()),
+                ready: Condvar::new(),
+            })
+        }
+    }
+
+    /// Get a connector whose connections are accepted by this listener,
+    /// whatever host they are for.
+    pub fn connector(&self) -> PipeConnector {
+        PipeConnector {
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl NetworkListener for PipeListener {
+    type Stream = PipeStream;
+
+    fn accept(&mut self) -> ::Result<PipeStream> {
+        let mut pending = self.queue.pending.lock().unwrap();
+        loop {
+            match pending.pop_front() {
+                Some(stream) => return Ok(stream),
+                None => pending = self.queue.ready.wait(pending).unwrap()
+            }
+        }
+    }
+
+    fn local_addr(&mut self) -> io::Result<SocketAddr> {
+        Ok("127.0.0.1:0".parse().unwrap())
+    }
+}
+
+/// A `NetworkConnector` connecting to a `PipeListener` in memory.
+#[derive(Clone)]
+pub struct PipeConnector {
+    queue: Arc<Backlog>,
+}
+
+impl NetworkConnector for PipeConnector {
+    type Stream = PipeStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::Result<PipeStream> {
+        debug!("PipeConnector::connect({:?}, {:?}, {:?})", host, port, scheme);
+        let (client, server) = pipe();
+        self.queue.pending.lock().unwrap().push_back(server);
+        self.queue.ready.notify_one();
+        Ok(client)
+    }
+}
+
+/// A unique path in the temporary directory, whose file is removed when the
+/// `TempPath` is dropped.
+///
+/// Tests running at the same time each get their own path, and a failing
+/// test leaves nothing behind.
+#[derive(Debug)]
+pub struct TempPath {
+    path: PathBuf,
+}
+
+impl TempPath {
+    /// Create a new path, whose file name starts with `prefix`.
+    pub fn new(prefix: &str) -> TempPath {
+        static COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+        let name = format!("{}-{}-{}", prefix, precise_time_ns(),
+                           COUNT.fetch_add(1, Ordering::SeqCst));
+        TempPath {
+            path: env::temp_dir().join(name)
+        }
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::Shutdown;
+    use std::thread;
+
+    use client::Client;
+    use net::{NetworkConnector, NetworkListener, NetworkStream};
+    use server::{Request, Response, Server};
+    use super::{pipe, MockConnector, PipeListener, TempPath};
+
+    #[test]
+    fn test_pipe() {
+        let (mut a, mut b) = pipe();
+        a.write_all(b"ping").unwrap();
+        let mut buf = [0; 4];
+        assert_eq!(b.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"ping");
+
+        let t = thread::spawn(move || {
+            let mut s = String::new();
+            b.read_to_string(&mut s).unwrap();
+            s
+        });
+        a.write_all(b"pong").unwrap();
+        a.close(Shutdown::Write).unwrap();
+        assert_eq!(t.join().unwrap(), "pong");
+    }
+
+    #[test]
+    fn test_pipe_listener() {
+        let mut listener = PipeListener::new();
+        let mut client = listener.connector().connect("127.0.0.1", 80, "http").unwrap();
+        let mut server = listener.accept().unwrap();
+        client.write_all(b"hello").unwrap();
+        drop(client);
+        let mut s = String::new();
+        server.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "hello");
+    }
+
+    #[test]
+    fn test_client_and_server() {
+        let listener = PipeListener::new();
+        let client = Client::with_connector(listener.connector());
+        let mut listening = Server::new(listener).handle(|mut req: Request, res: Response| {
+            let mut body = Vec::new();
+            req.read_to_end(&mut body).unwrap();
+            res.send(&body).unwrap();
+        }).unwrap();
+
+        for body in &["first", "second"] {
+            let mut s = String::new();
+            client.post("http://example.domain/").body(*body)
+                .send().unwrap().read_to_string(&mut s).unwrap();
+            assert_eq!(s, *body);
+        }
+        listening.close().unwrap();
+    }
+
+    #[test]
+    fn test_mock_connector() {
+        let mut connector = MockConnector::new();
+        connector.add_response("http://127.0.0.1", "HTTP/1.1 204 No Content\r\n\r\n");
+        assert!(connector.connect("127.0.0.2", 80, "http").is_err());
+
+        let mut stream = connector.connect("127.0.0.1", 80, "http").unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut s = String::new();
+        stream.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "HTTP/1.1 204 No Content\r\n\r\n");
+
+        let streams = connector.streams();
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].written(), b"GET / HTTP/1.1\r\n\r\n");
+        assert!(!streams[0].is_closed());
+    }
+
+    #[test]
+    fn test_temp_path() {
+        use std::fs::File;
+
+        let a = TempPath::new("hyper-test-temp-path");
+        let b = TempPath::new("hyper-test-temp-path");
+        assert!(*a != *b);
+        File::create(&a).unwrap();
+        assert!(a.exists());
+        let path = a.to_path_buf();
+        drop(a);
+        assert!(!path.exists());
+    }
+}
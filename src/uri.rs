@@ -84,6 +84,22 @@ impl Display for RequestUri {
     }
 }
 
+/// Percent-encodes everything but the unreserved characters of RFC 3986, as
+/// OAuth 1.0 and AWS Signature Version 4 both require.
+#[doc(hidden)]
+pub fn percent_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'A'...b'Z' | b'a'...b'z' | b'0'...b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            },
+            _ => encoded.push_str(&format!("%{:02X}", b))
+        }
+    }
+    encoded
+}
+
 #[test]
 fn test_uri_fromstr() {
     fn read(s: &str, result: RequestUri) {
@@ -108,3 +124,8 @@ fn test_uri_display() {
     assert_display("/", RequestUri::AbsolutePath("/".to_owned()));
 
 }
+
+#[test]
+fn test_percent_encode() {
+    assert_eq!(percent_encode(b"a-Z_0.~ /%\xFF"), "a-Z_0.~%20%2F%25%FF");
+}